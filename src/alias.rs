@@ -0,0 +1,130 @@
+use crate::config::Config;
+use crate::log::Log;
+use std::collections::HashMap;
+
+// 発言者名を正式名にまとめる
+pub struct NameResolver {
+    aliases: HashMap<String, String>,
+    merge_names: bool,
+}
+
+impl NameResolver {
+    pub fn new(config: &Config) -> NameResolver {
+        let mut aliases = HashMap::new();
+        for (name, alias_list) in &config.aliases {
+            for alias in alias_list {
+                aliases.insert(alias.clone(), name.clone());
+            }
+        }
+        NameResolver {
+            aliases,
+            merge_names: config.merge_names,
+        }
+    }
+
+    pub fn resolve(&self, name: &str) -> String {
+        // 別名リストを優先する
+        if let Some(canonical) = self.aliases.get(name) {
+            return canonical.clone();
+        }
+        if !self.merge_names {
+            return name.to_string();
+        }
+
+        let stripped = strip_parentheticals(name);
+        match self.aliases.get(&stripped) {
+            Some(canonical) => canonical.clone(),
+            None => stripped,
+        }
+    }
+
+    pub fn apply(&self, logs: &mut [Log]) {
+        for log in logs {
+            log.name = self.resolve(&log.name);
+        }
+    }
+}
+
+// 「イオリ (いおり)」「イオリ（重傷）」のような括弧書きを取り除く
+pub fn strip_parentheticals(name: &str) -> String {
+    let mut s = String::new();
+    let mut depth = 0;
+    for c in name.chars() {
+        match c {
+            '(' | '（' => depth += 1,
+            ')' | '）' if depth > 0 => depth -= 1,
+            _ if depth == 0 => s.push(c),
+            _ => {}
+        }
+    }
+
+    let s = s.trim().to_string();
+    // 名前がすべて括弧書きだった場合はそのまま残す
+    if s.is_empty() {
+        name.trim().to_string()
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_parentheticals() {
+        assert_eq!(strip_parentheticals("イオリ (いおり)"), "イオリ");
+        assert_eq!(strip_parentheticals("イオリ(重傷)"), "イオリ");
+        assert_eq!(strip_parentheticals("イオリ（重傷）"), "イオリ");
+        assert_eq!(strip_parentheticals("イオリ"), "イオリ");
+        assert_eq!(strip_parentheticals("(匿名)"), "(匿名)");
+    }
+
+    #[test]
+    fn test_resolve_with_aliases() {
+        let mut config = Config {
+            merge_names: true,
+            ..Config::default()
+        };
+        config
+            .aliases
+            .insert("イオリ".to_string(), vec!["いおりん".to_string()]);
+        let resolver = NameResolver::new(&config);
+        assert_eq!(resolver.resolve("いおりん"), "イオリ");
+        assert_eq!(resolver.resolve("いおりん(重傷)"), "イオリ");
+        assert_eq!(resolver.resolve("イオリ (いおり)"), "イオリ");
+        assert_eq!(resolver.resolve("KP"), "KP");
+    }
+
+    #[test]
+    fn test_resolve_without_heuristic() {
+        let resolver = NameResolver::new(&Config::default());
+        assert_eq!(resolver.resolve("イオリ (いおり)"), "イオリ (いおり)");
+        assert_eq!(resolver.resolve("警官(A)"), "警官(A)");
+        assert_eq!(resolver.resolve("警官(B)"), "警官(B)");
+    }
+
+    #[test]
+    fn test_apply_merges_logs() {
+        let mut logs = vec![
+            Log {
+                tab: "メイン".to_string(),
+                name: "イオリ (いおり)".to_string(),
+                texts: vec![],
+                color: String::new(),
+            },
+            Log {
+                tab: "メイン".to_string(),
+                name: "イオリ(重傷)".to_string(),
+                texts: vec![],
+                color: String::new(),
+            },
+        ];
+        let config = Config {
+            merge_names: true,
+            ..Config::default()
+        };
+        NameResolver::new(&config).apply(&mut logs);
+        assert!(logs.iter().all(|log| log.name == "イオリ"));
+    }
+}
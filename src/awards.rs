@@ -0,0 +1,305 @@
+use crate::alias::NameResolver;
+use crate::log::Log;
+use crate::role::RoleMap;
+use crate::roll::{Roll, RollResult};
+use crate::streak::Streaks;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+// セッションの表彰一件分
+pub struct Award {
+    pub title: &'static str,
+    pub names: Vec<String>,
+    pub detail: String,
+}
+
+// 今回のハイライト
+pub struct Awards {
+    pub awards: Vec<Award>,
+}
+
+impl Awards {
+    pub fn new(logs: &[Log], rolls: &[Roll], roles: &RoleMap, resolver: &NameResolver) -> Awards {
+        let rolls: Vec<&Roll> = rolls.iter().filter(|r| roles.is_pc(r.name())).collect();
+        let mut awards = Vec::new();
+
+        // 最多ファンブル
+        // 組み合わせロールは一回のロールとして数える
+        let fumbles = count_by_name(
+            rolls
+                .iter()
+                .filter(|r| r.result == RollResult::Fumble && r.is_first_part()),
+        );
+        if let Some((names, count)) = max_by_count(&fumbles) {
+            awards.push(Award {
+                title: "最多ファンブル賞",
+                names,
+                detail: format!("{}回", count),
+            });
+        }
+
+        // 低い技能値でのクリティカル
+        if let Some(roll) = rolls
+            .iter()
+            .filter(|r| r.result == RollResult::Critical && r.target.is_some())
+            .min_by_key(|r| r.target)
+        {
+            awards.push(Award {
+                title: "起死回生賞",
+                names: vec![roll.name().to_string()],
+                detail: format!("{}でクリティカル", describe(roll)),
+            });
+        }
+
+        // 最長失敗連続
+        let streaks = longest_failure_streaks(&rolls);
+        if let Some((names, count)) = max_by_count(&streaks).filter(|(_, count)| *count >= 2) {
+            awards.push(Award {
+                title: "不屈の挑戦者賞",
+                names,
+                detail: format!("{}連続失敗", count),
+            });
+        }
+
+        // 高い技能値でのファンブル
+        if let Some(roll) = rolls
+            .iter()
+            .filter(|r| r.result == RollResult::Fumble && r.target.is_some())
+            .max_by_key(|r| r.target)
+        {
+            awards.push(Award {
+                title: "まさかの大惨事賞",
+                names: vec![roll.name().to_string()],
+                detail: format!("{}でファンブル", describe(roll)),
+            });
+        }
+
+        // 最多ロール
+        let roll_counts = count_by_name(rolls.iter().filter(|r| r.is_first_part()));
+        if let Some((names, count)) = max_by_count(&roll_counts) {
+            awards.push(Award {
+                title: "ダイスの申し子賞",
+                names,
+                detail: format!("{}回ロール", count),
+            });
+        }
+
+        // SAN値の減少量
+        // ステータス変更の名前は発言者名と違って別名がまとめられていないので、同じ規則でまとめる
+        let mut san_lost: HashMap<String, usize> = HashMap::new();
+        for (name, loss) in logs
+            .iter()
+            .flat_map(|log| &log.texts)
+            .filter_map(|t| parse_san_loss(t))
+        {
+            let name = resolver.resolve(&name);
+            if roles.is_pc(&name) {
+                *san_lost.entry(name).or_insert(0) += loss;
+            }
+        }
+        if let Some((names, loss)) = max_by_count(&san_lost) {
+            awards.push(Award {
+                title: "正気度喪失賞",
+                names,
+                detail: format!("SAN -{}", loss),
+            });
+        }
+
+        Awards { awards }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut s = String::new();
+        for award in &self.awards {
+            s.push_str(&format!(
+                "{}：{}（{}）\n",
+                award.title,
+                award.names.join("、"),
+                award.detail
+            ));
+        }
+        s
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut s = String::from("## 今回のハイライト\n\n");
+        if self.awards.is_empty() {
+            s.push_str("該当なし\n");
+        }
+        for award in &self.awards {
+            s.push_str(&format!(
+                "- **{}**：{}（{}）\n",
+                award.title,
+                award.names.join("、"),
+                award.detail
+            ));
+        }
+        s
+    }
+}
+
+impl Display for Awards {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+// 「【目星】（目標値5、出目3）」
+fn describe(roll: &Roll) -> String {
+    let skill = roll.skill.as_deref().unwrap_or(&roll.command);
+    match (roll.target, roll.value) {
+        (Some(target), Some(value)) => format!("【{}】（目標値{}、出目{}）", skill, target, value),
+        (Some(target), None) => format!("【{}】（目標値{}）", skill, target),
+        _ => format!("【{}】", skill),
+    }
+}
+
+fn count_by_name<'r, 'a: 'r>(
+    rolls: impl Iterator<Item = &'r &'r Roll<'a>>,
+) -> HashMap<String, usize> {
+    let mut map = HashMap::new();
+    for roll in rolls {
+        *map.entry(roll.name().to_string()).or_insert(0) += 1;
+    }
+    map
+}
+
+// 最大値とその名前（同率は全員）
+fn max_by_count(map: &HashMap<String, usize>) -> Option<(Vec<String>, usize)> {
+    let max = map.values().copied().max().filter(|max| *max > 0)?;
+    let mut names: Vec<String> = map
+        .iter()
+        .filter(|(_, count)| **count == max)
+        .map(|(name, _)| name.clone())
+        .collect();
+    names.sort();
+    Some((names, max))
+}
+
+fn longest_failure_streaks(rolls: &[&Roll]) -> HashMap<String, usize> {
+    let mut rolls_by_name: HashMap<String, Vec<&Roll>> = HashMap::new();
+    for roll in rolls {
+        rolls_by_name
+            .entry(roll.name().to_string())
+            .or_default()
+            .push(roll);
+    }
+    rolls_by_name
+        .into_iter()
+        .map(|(name, rolls)| {
+            let length = Streaks::new(&rolls).failure.map_or(0, |s| s.length);
+            (name, length)
+        })
+        .collect()
+}
+
+// ココフォリアのステータス変更「[ イオリ ] SAN : 55 → 52」から減少量を読む
+fn parse_san_loss(text: &str) -> Option<(String, usize)> {
+    let rest = text.trim().strip_prefix('[')?;
+    let (name, rest) = rest.split_once(']')?;
+    let (label, rest) = rest.split_once(':')?;
+    if !label.trim().starts_with("SAN") {
+        return None;
+    }
+    let (before, after) = rest.split_once('→')?;
+    let before: i64 = before.trim().parse().ok()?;
+    let after: i64 = after.trim().parse().ok()?;
+    if after >= before {
+        return None;
+    }
+    Some((name.trim().to_string(), (before - after) as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::roll::parse_rolls;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_san_loss() {
+        assert_eq!(
+            parse_san_loss("[ イオリ ] SAN : 55 → 52"),
+            Some(("イオリ".to_string(), 3))
+        );
+        assert_eq!(parse_san_loss("[ イオリ ] SAN : 52 → 55"), None);
+        assert_eq!(parse_san_loss("[ イオリ ] HP : 10 → 5"), None);
+        assert_eq!(parse_san_loss("SAN : 55 → 52"), None);
+    }
+
+    #[test]
+    fn test_awards() {
+        let logs = vec![
+            log(
+                "イオリ",
+                "CCB<=5 【製作】 (1D100<=5) ＞ 2 ＞ 決定的成功/スペシャル",
+            ),
+            log("イオリ", "CCB<=50 【回避】 (1D100<=50) ＞ 60 ＞ 失敗"),
+            log(
+                "ソウタ",
+                "CCB<=90 【図書館】 (1D100<=90) ＞ 99 ＞ 致命的失敗",
+            ),
+            log("ソウタ", "CCB<=50 【回避】 (1D100<=50) ＞ 70 ＞ 失敗"),
+            log("ソウタ", "CCB<=50 【回避】 (1D100<=50) ＞ 80 ＞ 失敗"),
+            log("ソウタ", "CCB<=50 【回避】 (1D100<=50) ＞ 10 ＞ 成功"),
+            log("KP", "CCB<=50 【拳】 (1D100<=50) ＞ 100 ＞ 致命的失敗"),
+            log("system", "[ イオリ ] SAN : 55 → 50"),
+            log("system", "[ ソウタ ] SAN : 60 → 58"),
+        ];
+        let config = Config::default();
+        let roles = RoleMap::detect(&logs, &config);
+        let rolls = parse_rolls(&logs);
+        let awards = Awards::new(&logs, &rolls, &roles, &NameResolver::new(&config));
+
+        let text = awards.to_text();
+        assert!(text.contains("最多ファンブル賞：ソウタ（1回）"));
+        assert!(text.contains("起死回生賞：イオリ（【製作】（目標値5、出目2）でクリティカル）"));
+        assert!(text.contains("不屈の挑戦者賞：ソウタ（3連続失敗）"));
+        assert!(
+            text.contains("まさかの大惨事賞：ソウタ（【図書館】（目標値90、出目99）でファンブル）")
+        );
+        assert!(text.contains("ダイスの申し子賞：ソウタ（4回ロール）"));
+        assert!(text.contains("正気度喪失賞：イオリ（SAN -5）"));
+
+        let markdown = awards.to_markdown();
+        assert!(markdown.starts_with("## 今回のハイライト\n\n"));
+        assert!(markdown.contains("- **最多ファンブル賞**：ソウタ（1回）\n"));
+    }
+
+    #[test]
+    fn test_no_awards() {
+        let config = Config::default();
+        let awards = Awards::new(
+            &[],
+            &[],
+            &RoleMap::detect(&[], &config),
+            &NameResolver::new(&config),
+        );
+        assert_eq!(awards.to_text(), "");
+        assert_eq!(awards.to_markdown(), "## 今回のハイライト\n\n該当なし\n");
+    }
+
+    #[test]
+    fn test_san_loss_with_alias() {
+        let mut config = Config::default();
+        config
+            .aliases
+            .insert("イオリ".to_string(), vec!["いおりん".to_string()]);
+        let logs = vec![
+            log("イオリ", "CCB<=50 【回避】 (1D100<=50) ＞ 60 ＞ 失敗"),
+            log("system", "[ いおりん ] SAN : 55 → 50"),
+        ];
+        let roles = RoleMap::detect(&logs, &config);
+        let awards = Awards::new(&logs, &[], &roles, &NameResolver::new(&config));
+        assert!(awards.to_text().contains("正気度喪失賞：イオリ（SAN -5）"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::log::Log;
+use crate::roll::strip_secret_prefix;
+use crate::shinobigami::TableRoll;
+use std::fmt::{Display, Formatter};
+
+// choiceやランダム表で決まった結果
+// 例：choice[イオリ,ソウタ] (choice[イオリ,ソウタ]) ＞ ソウタ
+// 例：ST (ST) ＞ シーン表(2) ＞ 血の臭いがあたりに充満している。
+#[derive(Debug, Clone)]
+pub struct RandomResult<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    // 「choice[イオリ,ソウタ]」「シーン表(2)」
+    pub source: String,
+    pub result: String,
+}
+
+impl<'a> RandomResult<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<RandomResult<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        if segments.len() < 2 {
+            return None;
+        }
+        let command = segments[0].to_uppercase();
+        let command = strip_secret_prefix(&command).unwrap_or(&command);
+        if command.starts_with("CHOICE") {
+            // 後ろの「(choice[...])」はBCDiceが付けた展開済みのコマンドなので除く
+            let source = match segments[0].rfind(" (") {
+                Some(end) => &segments[0][..end],
+                None => segments[0],
+            };
+            let result = segments[segments.len() - 1];
+            if result.is_empty() {
+                return None;
+            }
+            return Some(RandomResult {
+                log,
+                index,
+                source: source.trim().to_string(),
+                result: result.to_string(),
+            });
+        }
+
+        let table = TableRoll::parse(log, index, text)?;
+        Some(RandomResult {
+            log,
+            index,
+            source: format!("{}({})", table.table, table.dice),
+            result: table.result,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.log.name
+    }
+}
+
+impl Display for RandomResult<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}件目 {}：{} ＞ {}",
+            self.index + 1,
+            self.name(),
+            self.source,
+            self.result
+        )
+    }
+}
+
+// ログの順に並ぶ
+pub fn parse_random_results(logs: &[Log]) -> Vec<RandomResult<'_>> {
+    let mut results = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(result) = RandomResult::parse(log, index, text) {
+                results.push(result);
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(name: &str, texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        let log = log(
+            "KP",
+            &[
+                "choice[イオリ,ソウタ] (choice[イオリ,ソウタ]) ＞ ソウタ",
+                "S choice 右 左 (choice 右 左) ＞ 左",
+                "CCB<=50 (1D100<=50) ＞ 22 ＞ 成功",
+                "1d6 (1D6) ＞ 3",
+            ],
+        );
+        let result = RandomResult::parse(&log, 4, &log.texts[0]).unwrap();
+        assert_eq!(result.source, "choice[イオリ,ソウタ]");
+        assert_eq!(result.result, "ソウタ");
+        assert_eq!(
+            result.to_string(),
+            "5件目 KP：choice[イオリ,ソウタ] ＞ ソウタ"
+        );
+        let result = RandomResult::parse(&log, 0, &log.texts[1]).unwrap();
+        assert_eq!(result.source, "S choice 右 左");
+        assert_eq!(result.result, "左");
+        assert!(RandomResult::parse(&log, 0, &log.texts[2]).is_none());
+        assert!(RandomResult::parse(&log, 0, &log.texts[3]).is_none());
+    }
+
+    #[test]
+    fn test_parse_random_results() {
+        let logs = [
+            log(
+                "イオリ",
+                &["ST (ST) ＞ シーン表(2) ＞ 血の臭いがあたりに充満している。"],
+            ),
+            log("KP", &["choice[A,B,C] (choice[A,B,C]) ＞ B"]),
+        ];
+        let results: Vec<String> = parse_random_results(&logs)
+            .iter()
+            .map(|result| result.to_string())
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                "1件目 イオリ：シーン表(2) ＞ 血の臭いがあたりに充満している。",
+                "2件目 KP：choice[A,B,C] ＞ B",
+            ]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::error::{MyError, ParseError};
+use crate::system::GameSystem;
+use crate::verify::CriticalRule;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+// 設定ファイルの書式
+//
+// # コメント
+// [alias]
+// イオリ = イオリ (いおり), イオリ(重傷)
+//
+// [player]
+// イオリ = 山田
+//
+// # KP・NPC・PCとして扱う名前、KP/NPC用のタブ（1行に1つ、またはカンマ区切り）
+// [gm]
+// KP
+// [npc]
+// 警官, 店主
+// [pc]
+// [gm_tab]
+// KP用
+//
+// [settings]
+// merge_names = true
+// npc_color_threshold = 3
+// # coc6・emoklore・sw25・dx3・shinobigami・insaneのどれか、autoまたは省略で自動判定
+// system = coc6
+// reroll_window = 3
+// # coc6（1～5/96～100）・01/100・scaled（目標値の1/10）のどれか、省略でハウスルールなし
+// critical_rule = 01/100
+#[derive(Debug, Clone)]
+pub struct Config {
+    // 正式名 -> 別名の一覧
+    pub aliases: HashMap<String, Vec<String>>,
+    // キャラクター名 -> プレイヤー名
+    pub players: HashMap<String, String>,
+    pub gm_names: Vec<String>,
+    pub npc_names: Vec<String>,
+    pub pc_names: Vec<String>,
+    // このタブでしか発言しない名前はNPCとして扱う
+    pub gm_tabs: Vec<String>,
+    // ルビや括弧書きを取り除いて名前をまとめるか
+    // 「警官(A)」「警官(B)」のような別人までまとまってしまうので既定では行わない
+    pub merge_names: bool,
+    // 同じ文字色をこの数以上の名前が使っていたらNPCとみなす（既定の0で無効）
+    pub npc_color_threshold: usize,
+    // Noneのときはログのダイスコマンドから判定する
+    pub system: Option<GameSystem>,
+    // 失敗から何件以内に同じ技能を振ったら振り直しを疑うか
+    pub reroll_window: usize,
+    // 1D100判定を判定し直すクリティカル・ファンブルのハウスルール
+    pub critical_rule: Option<CriticalRule>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            aliases: HashMap::new(),
+            players: HashMap::new(),
+            gm_names: Vec::new(),
+            npc_names: Vec::new(),
+            pc_names: Vec::new(),
+            gm_tabs: Vec::new(),
+            merge_names: false,
+            npc_color_threshold: 0,
+            system: None,
+            reroll_window: 3,
+            critical_rule: None,
+        }
+    }
+}
+
+struct Entry {
+    line: usize,
+    section: String,
+    key: String,
+    value: Option<String>,
+}
+
+impl Config {
+    pub fn load(filename: &str) -> Result<Config, MyError> {
+        let mut file = File::open(filename)?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        Ok(Config::parse(&text)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Config, ParseError> {
+        let mut config = Config::default();
+        for entry in parse_entries(text)? {
+            match entry.section.as_str() {
+                "alias" => {
+                    let Some(value) = entry.value else {
+                        return Err(entry_error(&entry, "別名が指定されていません"));
+                    };
+                    config
+                        .aliases
+                        .entry(entry.key)
+                        .or_default()
+                        .extend(split_list(&value));
+                }
+                "player" => {
+                    let Some(value) = entry.value else {
+                        return Err(entry_error(&entry, "プレイヤー名が指定されていません"));
+                    };
+                    config.players.insert(entry.key, value);
+                }
+                "gm" => config.gm_names.extend(split_list(&entry.key)),
+                "npc" => config.npc_names.extend(split_list(&entry.key)),
+                "pc" => config.pc_names.extend(split_list(&entry.key)),
+                "gm_tab" => config.gm_tabs.extend(split_list(&entry.key)),
+                "settings" => config.apply_setting(&entry)?,
+                _ => {
+                    return Err(entry_error(
+                        &entry,
+                        &format!("不明なセクションです：[{}]", entry.section),
+                    ))
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    fn apply_setting(&mut self, entry: &Entry) -> Result<(), ParseError> {
+        let value = entry.value.as_deref().unwrap_or("");
+        match entry.key.as_str() {
+            "merge_names" => self.merge_names = parse_bool(entry, value)?,
+            "npc_color_threshold" => self.npc_color_threshold = parse_number(entry, value)?,
+            "reroll_window" => self.reroll_window = parse_number(entry, value)?,
+            "system" if value.trim().eq_ignore_ascii_case("auto") => self.system = None,
+            "system" => {
+                self.system =
+                    Some(GameSystem::from_name(value).map_err(|e| entry_error(entry, &e.string))?)
+            }
+            "critical_rule" => {
+                self.critical_rule = Some(
+                    CriticalRule::from_name(value).map_err(|e| entry_error(entry, &e.string))?,
+                )
+            }
+            _ => {
+                return Err(entry_error(
+                    entry,
+                    &format!("不明な設定項目です：{}", entry.key),
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_entries(text: &str) -> Result<Vec<Entry>, ParseError> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+        if section.is_empty() {
+            return Err(ParseError {
+                string: format!("{}行目：セクションの外に設定があります", line_number),
+            });
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (line, None),
+        };
+        entries.push(Entry {
+            line: line_number,
+            section: section.clone(),
+            key: key.to_string(),
+            value,
+        });
+    }
+    Ok(entries)
+}
+
+fn entry_error(entry: &Entry, message: &str) -> ParseError {
+    ParseError {
+        string: format!("{}行目：{}", entry.line, message),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split([',', '、'])
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_bool(entry: &Entry, value: &str) -> Result<bool, ParseError> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(entry_error(
+            entry,
+            &format!("{}にはtrueかfalseを指定してください", entry.key),
+        )),
+    }
+}
+
+fn parse_number(entry: &Entry, value: &str) -> Result<usize, ParseError> {
+    value.parse().map_err(|_| {
+        entry_error(
+            entry,
+            &format!("{}には0以上の整数を指定してください", entry.key),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_aliases() {
+        let text = "# コメント\n[alias]\nイオリ = イオリ (いおり), イオリ(重傷)\nイオリ = いおりん\n";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(
+            config.aliases["イオリ"],
+            vec!["イオリ (いおり)", "イオリ(重傷)", "いおりん"]
+        );
+        assert!(!config.merge_names);
+    }
+
+    #[test]
+    fn test_parse_players() {
+        let config = Config::parse("[player]\nイオリ = 山田\nソウタ = 山田\n").unwrap();
+        assert_eq!(config.players["イオリ"], "山田");
+        assert_eq!(config.players["ソウタ"], "山田");
+        assert!(Config::parse("[player]\nイオリ").is_err());
+    }
+
+    #[test]
+    fn test_parse_role_lists() {
+        let text = "[gm]\nKP\n[npc]\n警官, 店主\n怪物\n[gm_tab]\nKP用\n";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.gm_names, vec!["KP"]);
+        assert_eq!(config.npc_names, vec!["警官", "店主", "怪物"]);
+        assert_eq!(config.gm_tabs, vec!["KP用"]);
+        assert!(config.pc_names.is_empty());
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let text = "[settings]\nmerge_names = true\nnpc_color_threshold = 3\nsystem = coc6\nreroll_window = 5\n";
+        let config = Config::parse(text).unwrap();
+        assert!(config.merge_names);
+        assert_eq!(config.npc_color_threshold, 3);
+        assert_eq!(config.reroll_window, 5);
+        assert_eq!(config.system, Some(GameSystem::Coc6));
+
+        let config = Config::parse("[settings]\nsystem = auto\n").unwrap();
+        assert_eq!(config.system, None);
+        assert_eq!(config.critical_rule, None);
+
+        let config = Config::parse("[settings]\ncritical_rule = 01/100\n").unwrap();
+        assert_eq!(config.critical_rule, Some(CriticalRule::Strict));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Config::parse("イオリ = いおり").is_err());
+        assert!(Config::parse("[unknown]\na = b").is_err());
+        assert!(Config::parse("[settings]\nmerge_names = maybe").is_err());
+        assert!(Config::parse("[settings]\nnpc_color_threshold = -1").is_err());
+        assert!(Config::parse("[settings]\nsystem = dnd").is_err());
+        assert!(Config::parse("[settings]\ncritical_rule = 1-10").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::roll::{normalize_skill, Roll, VARIABLE_SKILLS};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+// 同じPC・同じ技能なのに目標値が食い違っているもの
+pub struct Inconsistency {
+    pub name: String,
+    pub skill: String,
+    // 目標値 -> その目標値で振ったログ内の位置
+    pub targets: BTreeMap<u32, Vec<usize>>,
+}
+
+impl Display for Inconsistency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let targets = self
+            .targets
+            .iter()
+            .map(|(target, indices)| {
+                let positions = indices
+                    .iter()
+                    .map(|i| (i + 1).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}（{}件目）", target, positions)
+            })
+            .collect::<Vec<_>>()
+            .join(" / ");
+        write!(f, "{}【{}】：{}", self.name, self.skill, targets)
+    }
+}
+
+pub fn find_inconsistencies(rolls_by_name: &HashMap<String, Vec<&Roll>>) -> Vec<Inconsistency> {
+    let mut inconsistencies = Vec::new();
+    for (name, rolls) in rolls_by_name {
+        let mut targets_by_skill: BTreeMap<String, BTreeMap<u32, Vec<usize>>> = BTreeMap::new();
+        for roll in rolls {
+            let (Some(skill), Some(target)) = (&roll.skill, roll.target) else {
+                continue;
+            };
+            // 「目星+10」のような補正つきのロールは目標値が変わって当然なので除く
+            let normalized = normalize_skill(skill);
+            if has_modifier(skill) || VARIABLE_SKILLS.contains(&normalized.as_str()) {
+                continue;
+            }
+            targets_by_skill
+                .entry(normalized)
+                .or_default()
+                .entry(target)
+                .or_default()
+                .push(roll.index);
+        }
+
+        for (skill, targets) in targets_by_skill {
+            if targets.len() > 1 {
+                inconsistencies.push(Inconsistency {
+                    name: name.clone(),
+                    skill,
+                    targets,
+                });
+            }
+        }
+    }
+    inconsistencies.sort_by(|a, b| (&a.name, &a.skill).cmp(&(&b.name, &b.skill)));
+    inconsistencies
+}
+
+pub(crate) fn has_modifier(skill: &str) -> bool {
+    skill
+        .trim_end()
+        .ends_with(|c: char| "+-*/×0123456789＋－＊／０１２３４５６７８９".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_find_inconsistencies() {
+        let logs = vec![
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"),
+            log("イオリ", "CCB<=35 【目星+10】 (1D100<=35) ＞ 50 ＞ 失敗"),
+            log("イオリ", "CCB<=75 【目星】 (1D100<=75) ＞ 50 ＞ 成功"),
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 90 ＞ 失敗"),
+            log(
+                "イオリ",
+                "CCB<=60 【SAN値チェック】 (1D100<=60) ＞ 20 ＞ 成功",
+            ),
+            log(
+                "イオリ",
+                "CCB<=57 【SAN値チェック】 (1D100<=57) ＞ 20 ＞ 成功",
+            ),
+            log("ソウタ", "CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"),
+        ];
+        let rolls = parse_rolls(&logs);
+        let mut rolls_by_name: HashMap<String, Vec<&Roll>> = HashMap::new();
+        for roll in &rolls {
+            rolls_by_name
+                .entry(roll.name().to_string())
+                .or_default()
+                .push(roll);
+        }
+
+        let inconsistencies = find_inconsistencies(&rolls_by_name);
+        assert_eq!(inconsistencies.len(), 1);
+        assert_eq!(inconsistencies[0].skill, "目星");
+        assert_eq!(inconsistencies[0].targets[&25], vec![0, 3]);
+        assert_eq!(inconsistencies[0].targets[&75], vec![2]);
+        assert_eq!(
+            inconsistencies[0].to_string(),
+            "イオリ【目星】：25（1, 4件目） / 75（3件目）"
+        );
+    }
+}
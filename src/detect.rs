@@ -0,0 +1,241 @@
+use crate::log::Log;
+use crate::roll::strip_secret_prefix;
+use crate::system::GameSystem;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+// 自動判定で候補にするシステム
+const CANDIDATES: [GameSystem; 6] = [
+    GameSystem::Coc6,
+    GameSystem::Emoklore,
+    GameSystem::Sw25,
+    GameSystem::Dx3,
+    GameSystem::Shinobigami,
+    GameSystem::Insane,
+];
+
+// シノビガミ・インセインのシーン表・ファンブル表・感情表・ランダム特技決定表など
+const SCENE_TABLES: [&str; 7] = ["ST", "FT", "ET", "WT", "BT", "RTT", "RCT"];
+// インセインにしかない表
+// ホラースケープ表・指定特技表・遭遇表
+const INSANE_TABLES: [&str; 15] = [
+    "CHT", "VHT", "IHT", "RHT", "MHT", "LHT", "TVT", "TET", "TPT", "TST", "TKT", "TMT", "ECT",
+    "EMT", "EAT",
+];
+
+// ダイスコマンドから推定したゲームシステム
+pub struct Detection {
+    pub system: GameSystem,
+    // 推定に使ったロールのうち、選んだシステムのものの割合
+    pub confidence: f64,
+    // システム -> それらしいロールの数
+    pub counts: HashMap<GameSystem, usize>,
+}
+
+impl Detection {
+    pub fn new(logs: &[Log]) -> Detection {
+        let mut counts: HashMap<GameSystem, usize> = HashMap::new();
+        for log in logs {
+            for text in &log.texts {
+                if let Some(system) = classify(text) {
+                    *counts.entry(system).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // 2D6判定や共通の表はシノビガミとして数えているので、
+        // インセインにしかない表が振られていればインセインのものとみなす
+        if counts.contains_key(&GameSystem::Insane) {
+            if let Some(shared) = counts.remove(&GameSystem::Shinobigami) {
+                *counts.entry(GameSystem::Insane).or_insert(0) += shared;
+            }
+        }
+
+        let total: usize = counts.values().sum();
+        // 同数なら候補の並び順で先のものを選ぶ
+        let (system, count) = CANDIDATES
+            .iter()
+            .map(|system| (*system, counts.get(system).copied().unwrap_or(0)))
+            .fold((GameSystem::Coc6, 0), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+        let confidence = if total == 0 {
+            0.0
+        } else {
+            count as f64 / total as f64
+        };
+        Detection {
+            system,
+            confidence,
+            counts,
+        }
+    }
+
+    pub fn rolls(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+impl Display for Detection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.rolls() == 0 {
+            return write!(
+                f,
+                "{}（判定に使えるロールがないため既定値）",
+                self.system.to_display_string()
+            );
+        }
+        write!(
+            f,
+            "{}（自動判定、確信度{:.0}%、{}件中{}件）",
+            self.system.to_display_string(),
+            self.confidence * 100.0,
+            self.rolls(),
+            self.counts.get(&self.system).copied().unwrap_or(0)
+        )
+    }
+}
+
+// 一つのチャットがどのシステムのロールらしいか
+fn classify(text: &str) -> Option<GameSystem> {
+    let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+    if segments.len() < 2 {
+        return None;
+    }
+    let command = segments[0].to_uppercase();
+    // シークレットダイスの「S」は無視する
+    let command = strip_secret_prefix(&command).unwrap_or(&command);
+    let last = segments[segments.len() - 1];
+
+    if command.starts_with("CC") || command.starts_with("CBR") || command.starts_with("RES") {
+        Some(GameSystem::Coc6)
+    } else if command.contains("DM<=") {
+        Some(GameSystem::Emoklore)
+    } else if command.starts_with(|c: char| c.is_ascii_digit()) && command.contains("DX") {
+        Some(GameSystem::Dx3)
+    } else if text.contains("KeyNo.") {
+        Some(GameSystem::Sw25)
+    } else if command.contains("2D6") && command.contains(">=") {
+        // 2D6判定は結果の書き方で見分ける
+        if last.contains("自動的成功") || last.contains("自動的失敗") {
+            Some(GameSystem::Sw25)
+        } else if last.starts_with("スペシャル") || last.starts_with("ファンブル") {
+            Some(GameSystem::Shinobigami)
+        } else {
+            None
+        }
+    } else if segments.iter().any(|s| s.contains("表(")) {
+        match command.split_whitespace().next() {
+            Some(first) if INSANE_TABLES.contains(&first) => Some(GameSystem::Insane),
+            Some(first) if SCENE_TABLES.contains(&first) => Some(GameSystem::Shinobigami),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "PC1".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("CCB<=50 (1D100<=50) ＞ 22 ＞ 成功"),
+            Some(GameSystem::Coc6)
+        );
+        assert_eq!(
+            classify("S CCB<=50 (1D100<=50) ＞ 22 ＞ 成功"),
+            Some(GameSystem::Coc6)
+        );
+        assert_eq!(
+            classify("3DM<=6 (3DM<=6) ＞ [1, 5, 10] ＞ 2 ＞ ダブル！"),
+            Some(GameSystem::Emoklore)
+        );
+        assert_eq!(
+            classify("5DX+2@8 (5DX8+2) ＞ 10[2,5,6,8,9]+4[4]+2 ＞ 16"),
+            Some(GameSystem::Dx3)
+        );
+        assert_eq!(
+            classify("K20+10 (KeyNo.20+10) ＞ 2D:[3,4]=7 ＞ 5+10 ＞ 15"),
+            Some(GameSystem::Sw25)
+        );
+        assert_eq!(
+            classify("2d6+5>=12 (2D6+5>=12) ＞ 12[6,6]+5 ＞ 17 ＞ 自動的成功"),
+            Some(GameSystem::Sw25)
+        );
+        assert_eq!(
+            classify("2D6>=5 (2D6>=5) ＞ 2[1,1] ＞ 2 ＞ ファンブル"),
+            Some(GameSystem::Shinobigami)
+        );
+        assert_eq!(
+            classify("RTT ＞ ランダム特技決定表(1,6) ＞ 『器術』屋外"),
+            Some(GameSystem::Shinobigami)
+        );
+        assert_eq!(
+            classify("ST (ST) ＞ シーン表(2) ＞ 血の臭いがあたりに充満している。"),
+            Some(GameSystem::Shinobigami)
+        );
+        assert_eq!(
+            classify("CHT (CHT) ＞ 会話ホラースケープ表(5) ＞ 相手の顔が一瞬、別人に見える。"),
+            Some(GameSystem::Insane)
+        );
+        assert_eq!(classify("2D6>=5 (2D6>=5) ＞ 8[3,5] ＞ 8 ＞ 成功"), None);
+        assert_eq!(classify("1d6 (1D6) ＞ 3"), None);
+        assert_eq!(classify("こんにちは"), None);
+    }
+
+    #[test]
+    fn test_detection() {
+        let logs = [log(&[
+            "3DM<=6 (3DM<=6) ＞ [1, 5, 10] ＞ 2 ＞ ダブル！",
+            "2DM<=4 (2DM<=4) ＞ [3, 8] ＞ 1 ＞ 成功！",
+            "3DM<=6 (3DM<=6) ＞ [2, 5, 9] ＞ 2 ＞ ダブル！",
+            "CCB<=50 (1D100<=50) ＞ 22 ＞ 成功",
+        ])];
+        let detection = Detection::new(&logs);
+        assert_eq!(detection.system, GameSystem::Emoklore);
+        assert_eq!(detection.confidence, 0.75);
+        assert_eq!(
+            detection.to_string(),
+            "エモクロアTRPG（自動判定、確信度75%、4件中3件）"
+        );
+    }
+
+    #[test]
+    fn test_detect_insane() {
+        let logs = [log(&[
+            "2D6>=5 (2D6>=5) ＞ 12[6,6] ＞ 12 ＞ スペシャル",
+            "ST (ST) ＞ シーン表(2) ＞ 血の臭いがあたりに充満している。",
+            "TKT (TKT) ＞ 指定特技（知識）表(4) ＞ 『民俗学』",
+        ])];
+        let detection = Detection::new(&logs);
+        assert_eq!(detection.system, GameSystem::Insane);
+        assert_eq!(detection.confidence, 1.0);
+
+        // インセインにしかない表がなければシノビガミのまま
+        let logs = [log(&["2D6>=5 (2D6>=5) ＞ 12[6,6] ＞ 12 ＞ スペシャル"])];
+        assert_eq!(Detection::new(&logs).system, GameSystem::Shinobigami);
+    }
+
+    #[test]
+    fn test_no_rolls() {
+        let detection = Detection::new(&[log(&["こんにちは"])]);
+        assert_eq!(detection.system, GameSystem::Coc6);
+        assert_eq!(detection.confidence, 0.0);
+    }
+}
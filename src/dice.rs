@@ -0,0 +1,264 @@
+use crate::log::Log;
+use crate::roll::{normalize_skill, parse_skill, strip_secret_prefix};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+// 成功・失敗のない、合計だけのダイスロール
+// 例：1d6+1d4 【ダメージ】 (1D6+1D4) ＞ 5[5]+2[2] ＞ 7
+#[derive(Debug, Clone)]
+pub struct DiceRoll<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    pub text: &'a str,
+    // 【】の技能名、なければダイスの式
+    pub label: String,
+    // 「1D6+1D4」
+    pub expression: String,
+    pub total: i32,
+    // 式から求めた合計の期待値
+    pub expected: f64,
+}
+
+impl<'a> DiceRoll<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &'a str) -> Option<DiceRoll<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        if segments.len() < 2 {
+            return None;
+        }
+        let total = segments[segments.len() - 1].parse().ok()?;
+        let expression = parse_expression(segments[0])?;
+        let expected = expected_value(&expression)?;
+        let label = match parse_skill(segments[0]) {
+            Some(skill) => format!("【{}】", normalize_skill(&skill)),
+            None => expression.clone(),
+        };
+        Some(DiceRoll {
+            log,
+            index,
+            text,
+            label,
+            expression,
+            total,
+            expected,
+        })
+    }
+}
+
+// 「(1D6+1D4)」のように展開済みの式があればそれを使う
+// 判定（<=や>=）や、DX・DMなどのシステム固有のコマンドは除く
+fn parse_expression(command: &str) -> Option<String> {
+    let command = command.to_uppercase();
+    let expression = match command.rfind('(') {
+        Some(start) => {
+            let rest = &command[start + 1..];
+            &rest[..rest.find(')')?]
+        }
+        None => {
+            let command = strip_secret_prefix(&command).unwrap_or(&command);
+            command.split_whitespace().next()?
+        }
+    };
+    let expression: String = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    if !expression.contains('D')
+        || !expression
+            .chars()
+            .all(|c| c.is_ascii_digit() || "D+-".contains(c))
+    {
+        return None;
+    }
+    Some(expression)
+}
+
+// 「2D6+1D4-1」の期待値
+fn expected_value(expression: &str) -> Option<f64> {
+    let mut expected = 0.0;
+    let mut sign = 1.0;
+    let mut term = String::new();
+    for c in expression.chars().chain(std::iter::once('+')) {
+        if c == '+' || c == '-' {
+            expected += sign * term_expected_value(&term)?;
+            sign = if c == '+' { 1.0 } else { -1.0 };
+            term.clear();
+        } else {
+            term.push(c);
+        }
+    }
+    Some(expected)
+}
+
+fn term_expected_value(term: &str) -> Option<f64> {
+    match term.split_once('D') {
+        Some((count, sides)) => {
+            let count: u32 = if count.is_empty() {
+                1
+            } else {
+                count.parse().ok()?
+            };
+            let sides: u32 = sides.parse().ok()?;
+            if sides == 0 {
+                return None;
+            }
+            Some(count as f64 * (sides as f64 + 1.0) / 2.0)
+        }
+        None => term.parse().ok(),
+    }
+}
+
+pub fn parse_dice_rolls(logs: &[Log]) -> Vec<DiceRoll<'_>> {
+    let mut rolls = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(roll) = DiceRoll::parse(log, index, text) {
+                rolls.push(roll);
+            }
+        }
+    }
+    rolls
+}
+
+// ラベルごとの集計
+#[derive(Debug, Default)]
+pub struct DiceStats {
+    pub count: usize,
+    pub total: i32,
+    pub expected: f64,
+    pub max: i32,
+}
+
+impl DiceStats {
+    fn add(&mut self, roll: &DiceRoll) {
+        self.max = if self.count == 0 {
+            roll.total
+        } else {
+            self.max.max(roll.total)
+        };
+        self.count += 1;
+        self.total += roll.total;
+        self.expected += roll.expected;
+    }
+
+    pub fn average(&self) -> f64 {
+        self.total as f64 / self.count.max(1) as f64
+    }
+
+    pub fn expected_average(&self) -> f64 {
+        self.expected / self.count.max(1) as f64
+    }
+}
+
+// 一人分の集計
+#[derive(Debug, Default)]
+pub struct DiceSummary {
+    pub all: DiceStats,
+    // 一度に出た最大の合計とそのラベル
+    pub biggest: Option<(i32, String)>,
+    // ラベル -> 集計
+    pub by_label: BTreeMap<String, DiceStats>,
+}
+
+impl DiceSummary {
+    pub fn new(rolls: &[&DiceRoll]) -> DiceSummary {
+        let mut summary = DiceSummary::default();
+        for roll in rolls {
+            summary.all.add(roll);
+            summary
+                .by_label
+                .entry(roll.label.clone())
+                .or_default()
+                .add(roll);
+            if summary
+                .biggest
+                .as_ref()
+                .is_none_or(|(total, _)| roll.total > *total)
+            {
+                summary.biggest = Some((roll.total, roll.label.clone()));
+            }
+        }
+        summary
+    }
+}
+
+impl Display for DiceSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ダイス：{}回（合計{}、期待値{:.1}）",
+            self.all.count, self.all.total, self.all.expected
+        )?;
+        if let Some((total, label)) = &self.biggest {
+            write!(f, "、最大{}（{}）", total, label)?;
+        }
+        writeln!(f)?;
+        for (label, stats) in &self.by_label {
+            writeln!(
+                f,
+                "  {}：{}回、平均{:.1}（期待値{:.1}）、最大{}",
+                label,
+                stats.count,
+                stats.average(),
+                stats.expected_average(),
+                stats.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_dice_roll() {
+        let log = log(&[
+            "1d6+1d4 【ダメージ】 (1D6+1D4) ＞ 5[5]+2[2] ＞ 7",
+            "1d10 (1D10) ＞ 8",
+            "CCB<=50 (1D100<=50) ＞ 22 ＞ 成功",
+            "2d6+5>=12 (2D6+5>=12) ＞ 7[3,4]+5 ＞ 12 ＞ 成功",
+            "5DX+2@8 (5DX8+2) ＞ 10[2,5,6,8,9]+4[4]+2 ＞ 16",
+            "K20+10 (KeyNo.20+10) ＞ 2D:[3,4]=7 ＞ 5+10 ＞ 15",
+        ]);
+        let roll = DiceRoll::parse(&log, 0, &log.texts[0]).unwrap();
+        assert_eq!(roll.label, "【ダメージ】");
+        assert_eq!(roll.expression, "1D6+1D4");
+        assert_eq!(roll.total, 7);
+        assert_eq!(roll.expected, 6.0);
+        let roll = DiceRoll::parse(&log, 0, &log.texts[1]).unwrap();
+        assert_eq!(roll.label, "1D10");
+        assert_eq!(roll.expected, 5.5);
+        for text in &log.texts[2..] {
+            assert!(DiceRoll::parse(&log, 0, text).is_none(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_expected_value() {
+        assert_eq!(expected_value("2D6+1D4-1"), Some(8.5));
+        assert_eq!(expected_value("D6"), Some(3.5));
+        assert_eq!(expected_value("1D0"), None);
+    }
+
+    #[test]
+    fn test_dice_summary() {
+        let log = log(&[
+            "1d6+1d4 【ダメージ】 (1D6+1D4) ＞ 5[5]+2[2] ＞ 7",
+            "1d6+1d4 【ダメージ】 (1D6+1D4) ＞ 1[1]+2[2] ＞ 3",
+            "1d10 (1D10) ＞ 9",
+        ]);
+        let rolls = parse_dice_rolls(std::slice::from_ref(&log));
+        let rolls: Vec<&DiceRoll> = rolls.iter().collect();
+        assert_eq!(
+            DiceSummary::new(&rolls).to_string(),
+            "ダイス：3回（合計19、期待値17.5）、最大9（1D10）\n  1D10：1回、平均9.0（期待値5.5）、最大9\n  【ダメージ】：2回、平均5.0（期待値6.0）、最大7\n"
+        );
+    }
+}
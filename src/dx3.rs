@@ -0,0 +1,183 @@
+use crate::log::Log;
+use crate::roll::{normalize_skill, parse_skill, NO_SKILL};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+// ダブルクロス3rdの判定一回分
+// 例：5DX+2@8 【白兵】 (5DX8+2) ＞ 10[2,5,6,8,9]+10[3,8]+4[4]+2 ＞ 26
+// 「[...]」の組が一つ増えるごとにクリティカルで振り足している
+#[derive(Debug, Clone)]
+pub struct Dx3Roll<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    pub skill: Option<String>,
+    // 達成値（ファンブルのときは0）
+    pub achievement: Option<i32>,
+    // クリティカルで振り足した回数
+    pub criticals: usize,
+    pub fumble: bool,
+}
+
+impl<'a> Dx3Roll<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<Dx3Roll<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        let command = segments.first()?.to_uppercase();
+        if !command.contains("DX") {
+            return None;
+        }
+        let position = segments.iter().position(|s| s.contains('['))?;
+        let groups = segments[position].matches('[').count();
+        Some(Dx3Roll {
+            log,
+            index,
+            skill: parse_skill(segments[0]),
+            achievement: segments.get(position + 1).and_then(|s| s.parse().ok()),
+            criticals: groups.saturating_sub(1),
+            fumble: segments.iter().any(|s| s.contains("ファンブル")),
+        })
+    }
+
+    pub fn skill_name(&self) -> String {
+        match &self.skill {
+            Some(skill) => normalize_skill(skill),
+            None => NO_SKILL.to_string(),
+        }
+    }
+}
+
+pub fn parse_dx3_rolls(logs: &[Log]) -> Vec<Dx3Roll<'_>> {
+    let mut rolls = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(roll) = Dx3Roll::parse(log, index, text) {
+                rolls.push(roll);
+            }
+        }
+    }
+    rolls
+}
+
+// 一人分の集計
+#[derive(Debug, Default)]
+pub struct Dx3Summary {
+    pub rolls: usize,
+    pub total_achievement: i32,
+    pub max_achievement: i32,
+    // クリティカルの合計回数と一度に続いた最大回数
+    pub criticals: usize,
+    pub max_chain: usize,
+    pub fumbles: usize,
+    // 技能 -> （回数、達成値の合計）
+    pub by_skill: BTreeMap<String, (usize, i32)>,
+}
+
+impl Dx3Summary {
+    pub fn new(rolls: &[&Dx3Roll]) -> Dx3Summary {
+        let mut summary = Dx3Summary::default();
+        for roll in rolls {
+            summary.rolls += 1;
+            summary.criticals += roll.criticals;
+            summary.max_chain = summary.max_chain.max(roll.criticals);
+            if roll.fumble {
+                summary.fumbles += 1;
+            }
+            let achievement = roll.achievement.unwrap_or(0);
+            summary.total_achievement += achievement;
+            summary.max_achievement = summary.max_achievement.max(achievement);
+            let entry = summary.by_skill.entry(roll.skill_name()).or_default();
+            entry.0 += 1;
+            entry.1 += achievement;
+        }
+        summary
+    }
+
+    pub fn average_achievement(&self) -> f64 {
+        if self.rolls == 0 {
+            0.0
+        } else {
+            self.total_achievement as f64 / self.rolls as f64
+        }
+    }
+}
+
+impl Display for Dx3Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "判定：{}回（達成値の平均{:.1}、最大{}）",
+            self.rolls,
+            self.average_achievement(),
+            self.max_achievement
+        )?;
+        writeln!(
+            f,
+            "クリティカル：{}回（最大{}連続） / ファンブル：{}回",
+            self.criticals, self.max_chain, self.fumbles
+        )?;
+        let skills = self
+            .by_skill
+            .iter()
+            .map(|(skill, (count, total))| {
+                format!(
+                    "《{}》平均{:.1}（{}回）",
+                    skill,
+                    *total as f64 / *count as f64,
+                    count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !skills.is_empty() {
+            writeln!(f, "技能別：{}", skills)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "ハヤト".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_dx3_roll() {
+        let log = log(&[
+            "5DX+2@8 【白兵】 (5DX8+2) ＞ 10[2,5,6,8,9]+10[3,8]+4[4]+2 ＞ 26",
+            "3DX (3DX10) ＞ 1[1,1,1] ＞ 0 ＞ ファンブル",
+            "CCB<=50 (1D100<=50) ＞ 22 ＞ 成功",
+        ]);
+        let roll = Dx3Roll::parse(&log, 0, &log.texts[0]).unwrap();
+        assert_eq!(roll.skill, Some("白兵".to_string()));
+        assert_eq!(roll.achievement, Some(26));
+        assert_eq!(roll.criticals, 2);
+        assert!(!roll.fumble);
+        let roll = Dx3Roll::parse(&log, 0, &log.texts[1]).unwrap();
+        assert_eq!(roll.achievement, Some(0));
+        assert!(roll.fumble);
+        assert!(Dx3Roll::parse(&log, 0, &log.texts[2]).is_none());
+    }
+
+    #[test]
+    fn test_dx3_summary() {
+        let log = log(&[
+            "5DX+2@8 【白兵】 (5DX8+2) ＞ 10[2,5,6,8,9]+10[3,8]+4[4]+2 ＞ 26",
+            "5DX@8 【白兵】 (5DX8) ＞ 7[1,2,3,5,7] ＞ 7",
+            "3DX 【知覚】 (3DX10) ＞ 1[1,1,1] ＞ 0 ＞ ファンブル",
+        ]);
+        let rolls = parse_dx3_rolls(std::slice::from_ref(&log));
+        let rolls: Vec<&Dx3Roll> = rolls.iter().collect();
+        let summary = Dx3Summary::new(&rolls);
+        assert_eq!(
+            summary.to_string(),
+            "判定：3回（達成値の平均11.0、最大26）\nクリティカル：2回（最大2連続） / ファンブル：1回\n技能別：《白兵》平均16.5（2回）, 《知覚》平均0.0（1回）\n"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::log::Log;
+use crate::roll::{parse_skill, parse_target};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+// エモクロアの判定一回分
+// 例：3DM<=6 【知覚】 (3DM<=6) ＞ [1, 5, 10] ＞ 1 ＞ 成功！
+// 出目1は成功2つ分（クリティカル）、出目10は成功を1つ打ち消す（ファンブル）
+#[derive(Debug, Clone)]
+pub struct EmokloreRoll<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    pub skill: Option<String>,
+    pub target: Option<u32>,
+    pub dice: Vec<u32>,
+    pub successes: i32,
+    // 「成功」「ダブル」「ファンブル」など
+    pub outcome: String,
+}
+
+impl<'a> EmokloreRoll<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<EmokloreRoll<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        let command = segments.first()?.to_uppercase();
+        if !command.contains("DM<=") {
+            return None;
+        }
+        // 出目の並び・成功数・結果の順に並んでいる
+        let position = segments.iter().position(|s| s.starts_with('['))?;
+        let dice = segments[position]
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+            .map(|value| value.trim().parse().ok())
+            .collect::<Option<Vec<u32>>>()?;
+        let successes = segments.get(position + 1)?.parse().ok()?;
+        let outcome = segments
+            .get(position + 2)
+            .map(|s| s.trim_end_matches(['!', '！']).to_string())
+            .unwrap_or_default();
+
+        Some(EmokloreRoll {
+            log,
+            index,
+            skill: parse_skill(segments[0]),
+            target: parse_target(&command),
+            dice,
+            successes,
+            outcome,
+        })
+    }
+
+    pub fn criticals(&self) -> usize {
+        self.dice.iter().filter(|&&value| value == 1).count()
+    }
+
+    pub fn fumbles(&self) -> usize {
+        self.dice.iter().filter(|&&value| value == 10).count()
+    }
+}
+
+pub fn parse_emoklore_rolls(logs: &[Log]) -> Vec<EmokloreRoll<'_>> {
+    let mut rolls = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(roll) = EmokloreRoll::parse(log, index, text) {
+                rolls.push(roll);
+            }
+        }
+    }
+    rolls
+}
+
+// 一人分の成功数の集計
+#[derive(Debug, Default)]
+pub struct EmokloreSummary {
+    pub rolls: usize,
+    pub total_successes: i32,
+    // 出目1・出目10の数
+    pub criticals: usize,
+    pub fumbles: usize,
+    // 結果 -> 回数
+    pub outcomes: BTreeMap<String, usize>,
+}
+
+impl EmokloreSummary {
+    pub fn new(rolls: &[&EmokloreRoll]) -> EmokloreSummary {
+        let mut summary = EmokloreSummary::default();
+        for roll in rolls {
+            summary.rolls += 1;
+            summary.total_successes += roll.successes;
+            summary.criticals += roll.criticals();
+            summary.fumbles += roll.fumbles();
+            if !roll.outcome.is_empty() {
+                *summary.outcomes.entry(roll.outcome.clone()).or_insert(0) += 1;
+            }
+        }
+        summary
+    }
+
+    pub fn average_successes(&self) -> f64 {
+        if self.rolls == 0 {
+            0.0
+        } else {
+            self.total_successes as f64 / self.rolls as f64
+        }
+    }
+}
+
+impl Display for EmokloreSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "判定：{}回", self.rolls)?;
+        writeln!(
+            f,
+            "成功数：合計{}（平均{:.2}）",
+            self.total_successes,
+            self.average_successes()
+        )?;
+        writeln!(
+            f,
+            "出目1（クリティカル）：{} / 出目10（ファンブル）：{}",
+            self.criticals, self.fumbles
+        )?;
+        if !self.outcomes.is_empty() {
+            let outcomes = self
+                .outcomes
+                .iter()
+                .map(|(outcome, count)| format!("{}（{}回）", outcome, count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(f, "結果：{}", outcomes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "アキラ".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_emoklore_roll() {
+        let log = log(&["3DM<=6 【知覚】 (3DM<=6) ＞ [1, 5, 10] ＞ 2 ＞ ダブル！"]);
+        let roll = EmokloreRoll::parse(&log, 4, &log.texts[0]).unwrap();
+        assert_eq!(roll.index, 4);
+        assert_eq!(roll.skill, Some("知覚".to_string()));
+        assert_eq!(roll.target, Some(6));
+        assert_eq!(roll.dice, vec![1, 5, 10]);
+        assert_eq!(roll.successes, 2);
+        assert_eq!(roll.outcome, "ダブル");
+        assert_eq!(roll.criticals(), 1);
+        assert_eq!(roll.fumbles(), 1);
+
+        assert!(EmokloreRoll::parse(&log, 0, "CCB<=50 (1D100<=50) ＞ 22 ＞ 成功").is_none());
+        assert!(EmokloreRoll::parse(&log, 0, "3DM<=6").is_none());
+    }
+
+    #[test]
+    fn test_emoklore_summary() {
+        let log = log(&[
+            "3DM<=6 (3DM<=6) ＞ [1, 5, 10] ＞ 2 ＞ ダブル！",
+            "2DM<=4 (2DM<=4) ＞ [3, 8] ＞ 1 ＞ 成功！",
+            "2DM<=4 (2DM<=4) ＞ [7, 10] ＞ -1 ＞ ファンブル！",
+        ]);
+        let rolls = parse_emoklore_rolls(std::slice::from_ref(&log));
+        let rolls: Vec<&EmokloreRoll> = rolls.iter().collect();
+        let summary = EmokloreSummary::new(&rolls);
+
+        assert_eq!(summary.rolls, 3);
+        assert_eq!(summary.total_successes, 2);
+        assert_eq!(summary.fumbles, 2);
+        assert_eq!(
+            summary.to_string(),
+            "判定：3回\n成功数：合計2（平均0.67）\n出目1（クリティカル）：1 / 出目10（ファンブル）：2\n結果：ダブル（1回）, ファンブル（1回）, 成功（1回）\n"
+        );
+    }
+}
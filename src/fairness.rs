@@ -0,0 +1,251 @@
+use crate::roll::{Roll, RollResult};
+use crate::stats::{chi_square, chi_square_p_value};
+use crate::system::GameSystem;
+use crate::verify::CriticalRule;
+use std::fmt::{Display, Formatter};
+
+// 1～10, 11～20, ..., 91～100の10区間に分ける
+const BINS: usize = 10;
+// 期待度数が5未満になるとカイ二乗検定が当てにならない
+const MIN_VALUES_FOR_TEST: usize = BINS * 5;
+
+// 1D100の出目の偏り
+pub struct Fairness {
+    pub histogram: [usize; BINS],
+    pub values: usize,
+    // 目標値のわかる出目の数（クリティカル・ファンブル率の分母）
+    pub targeted: usize,
+    pub criticals: usize,
+    pub fumbles: usize,
+    // 目標値から計算したクリティカル・ファンブル数の期待値
+    pub expected_criticals: f64,
+    pub expected_fumbles: f64,
+}
+
+impl Fairness {
+    // クリティカル・ファンブルはハウスルールがあればそれで、なければコマンドごとの範囲で数える
+    pub fn new(rolls: &[&Roll], system: GameSystem, house_rule: Option<CriticalRule>) -> Fairness {
+        let mut fairness = Fairness {
+            histogram: [0; BINS],
+            values: 0,
+            targeted: 0,
+            criticals: 0,
+            fumbles: 0,
+            expected_criticals: 0.0,
+            expected_fumbles: 0.0,
+        };
+
+        for roll in rolls {
+            // 組み合わせロールは一つの出目を技能の数だけ使っているので一度だけ数える
+            if !roll.is_d100() || !roll.is_first_part() {
+                continue;
+            }
+            let Some(value @ 1..=100) = roll.value else {
+                continue;
+            };
+            fairness.histogram[(value as usize - 1) / 10] += 1;
+            fairness.values += 1;
+
+            let Some(target) = roll.target else {
+                continue;
+            };
+            fairness.targeted += 1;
+            let result = match house_rule {
+                Some(rule) => rule.result(value, target),
+                None => roll.result,
+            };
+            match result {
+                RollResult::Critical => fairness.criticals += 1,
+                RollResult::Fumble => fairness.fumbles += 1,
+                _ => {}
+            }
+            let rule = house_rule.unwrap_or(CriticalRule::for_roll(&roll.command, system));
+            let (critical, fumble) = rule.probabilities(target);
+            fairness.expected_criticals += critical;
+            fairness.expected_fumbles += fumble;
+        }
+        fairness
+    }
+
+    pub fn chi_square(&self) -> f64 {
+        let expected = [self.values as f64 / BINS as f64; BINS];
+        chi_square(&self.histogram, &expected)
+    }
+
+    // 出目が一様分布に従うという仮説のp値
+    // 出目が少なすぎる場合はNone
+    pub fn p_value(&self) -> Option<f64> {
+        if self.values < MIN_VALUES_FOR_TEST {
+            return None;
+        }
+        Some(chi_square_p_value(self.chi_square(), BINS - 1))
+    }
+
+    fn rate(count: usize, total: usize) -> f64 {
+        if total == 0 {
+            0.0
+        } else {
+            count as f64 / total as f64 * 100.0
+        }
+    }
+}
+
+impl Display for Fairness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::new();
+        s.push_str(&format!("1D100の出目：{}回\n", self.values));
+        let max = self.histogram.iter().max().copied().unwrap_or(0).max(1);
+        for (i, count) in self.histogram.iter().enumerate() {
+            // 最大の区間が20文字になるように棒グラフを描く
+            let bar = "■".repeat(count * 20 / max);
+            let line = format!("{:>3}～{:>3}：{:>4} {}", i * 10 + 1, i * 10 + 10, count, bar);
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+
+        match self.p_value() {
+            Some(p_value) => {
+                let verdict = if p_value < 0.05 {
+                    "偏りあり（呪われているかも）"
+                } else {
+                    "偏りなし"
+                };
+                s.push_str(&format!(
+                    "カイ二乗値：{:.2}（p値：{:.3}）→ {}\n",
+                    self.chi_square(),
+                    p_value,
+                    verdict
+                ));
+            }
+            None => s.push_str(&format!(
+                "カイ二乗検定：出目が{}回未満のため判定できません\n",
+                MIN_VALUES_FOR_TEST
+            )),
+        }
+
+        s.push_str(&format!(
+            "クリティカル率：{:.1}%（期待値：{:.1}%）\n",
+            Fairness::rate(self.criticals, self.targeted),
+            self.expected_criticals / self.targeted.max(1) as f64 * 100.0
+        ));
+        s.push_str(&format!(
+            "ファンブル率：{:.1}%（期待値：{:.1}%）\n",
+            Fairness::rate(self.fumbles, self.targeted),
+            self.expected_fumbles / self.targeted.max(1) as f64 * 100.0
+        ));
+        write!(f, "{s}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(texts: Vec<String>) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "PC1".to_string(),
+            texts,
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_uniform_values() {
+        let texts = (1..=100)
+            .map(|value| {
+                let result = match value {
+                    1..=5 => "決定的成功/スペシャル",
+                    6..=50 => "成功",
+                    96..=100 => "致命的失敗",
+                    _ => "失敗",
+                };
+                format!("CCB<=50 (1D100<=50) ＞ {} ＞ {}", value, result)
+            })
+            .collect();
+        let logs = vec![log(texts)];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let fairness = Fairness::new(&rolls, GameSystem::Coc6, None);
+
+        assert_eq!(fairness.values, 100);
+        assert_eq!(fairness.histogram, [10; BINS]);
+        assert_eq!(fairness.chi_square(), 0.0);
+        assert_eq!(fairness.p_value(), Some(1.0));
+        assert_eq!(fairness.criticals, 5);
+        assert_eq!(fairness.fumbles, 5);
+        assert!((fairness.expected_criticals - 5.0).abs() < 1e-9);
+        assert!((fairness.expected_fumbles - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_combination_counted_once() {
+        let logs = vec![log(vec![
+            "CBRB(50,60) (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功".to_string(),
+            "CCB<=50 (1D100<=50) ＞ 30 ＞ 成功".to_string(),
+        ])];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let fairness = Fairness::new(&rolls, GameSystem::Coc6, None);
+
+        assert_eq!(rolls.len(), 3);
+        assert_eq!(fairness.values, 2);
+        assert_eq!(fairness.histogram[5], 1);
+    }
+
+    #[test]
+    fn test_expected_rates() {
+        let logs = vec![log(vec![
+            "CC<=50 (1D100<=50) ＞ 30 ＞ 成功".to_string(),
+            "1D100 (1D100) ＞ 45".to_string(),
+        ])];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+
+        // CCは01/100、目標値のない出目は分母に入れない
+        let fairness = Fairness::new(&rolls, GameSystem::Coc6, None);
+        assert_eq!(fairness.targeted, 1);
+        assert!((fairness.expected_criticals - 0.01).abs() < 1e-9);
+        assert!((fairness.expected_fumbles - 0.01).abs() < 1e-9);
+        assert!(fairness
+            .to_string()
+            .contains("クリティカル率：0.0%（期待値：1.0%）"));
+
+        // ハウスルールがあればそれで数える
+        let fairness = Fairness::new(&rolls, GameSystem::Coc6, Some(CriticalRule::Scaled));
+        assert!((fairness.expected_criticals - 0.05).abs() < 1e-9);
+        assert!((fairness.expected_fumbles - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_biased_values() {
+        let texts = (0..60)
+            .map(|_| "CCB<=50 (1D100<=50) ＞ 99 ＞ 致命的失敗".to_string())
+            .collect();
+        let logs = vec![log(texts)];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let fairness = Fairness::new(&rolls, GameSystem::Coc6, None);
+
+        assert_eq!(fairness.histogram[9], 60);
+        assert!(fairness.p_value().unwrap() < 0.05);
+        assert!(fairness.to_string().contains("偏りあり"));
+    }
+
+    #[test]
+    fn test_too_few_values() {
+        let logs = vec![log(vec![
+            "CCB<=50 (1D100<=50) ＞ 30 ＞ 成功".to_string(),
+            "1D6 (1D6) ＞ 3 ＞ 成功".to_string(),
+        ])];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let fairness = Fairness::new(&rolls, GameSystem::Coc6, None);
+
+        assert_eq!(fairness.values, 1);
+        assert_eq!(fairness.p_value(), None);
+        assert!(fairness.to_string().contains("判定できません"));
+    }
+}
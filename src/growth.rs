@@ -0,0 +1,208 @@
+use crate::roll::{Roll, RollKind, NO_SKILL, RESISTANCE_SKILL};
+use std::fmt::{Display, Formatter};
+
+// CoC6で成長判定の対象にならないもの
+const NOT_GROWABLE: [&str; 17] = [
+    NO_SKILL,
+    RESISTANCE_SKILL,
+    "SAN値チェック",
+    "SANチェック",
+    "正気度ロール",
+    "幸運",
+    "アイデア",
+    "知識",
+    "クトゥルフ神話",
+    "STR",
+    "CON",
+    "POW",
+    "DEX",
+    "APP",
+    "SIZ",
+    "INT",
+    "EDU",
+];
+
+pub struct GrowthCandidate {
+    pub skill: String,
+    // 最後に振ったときの目標値
+    pub target: Option<u32>,
+}
+
+// 一人分の成長判定の候補
+pub struct GrowthChecks {
+    pub candidates: Vec<GrowthCandidate>,
+}
+
+impl GrowthChecks {
+    // 成功・クリティカルしたロールから対象の技能を選び、最新の目標値を探す
+    // パレットを適用した後のロールを使うので、技能名のないロールも拾える
+    pub fn new(rolls: &[&Roll]) -> GrowthChecks {
+        // 「芸術(絵画)」「芸術(歌唱)」は専門分野ごとに別の候補にする
+        let mut skills: Vec<String> = Vec::new();
+        // 組み合わせロールは成功した技能だけを対象にする
+        // 対抗ロールは技能名がついていても成長しない
+        for roll in rolls
+            .iter()
+            .filter(|roll| roll.result.is_success() && roll.kind != RollKind::Resistance)
+        {
+            let skill = roll.skill_name();
+            if !NOT_GROWABLE.contains(&skill.as_str()) && !skills.contains(&skill) {
+                skills.push(skill);
+            }
+        }
+        skills.sort();
+
+        let candidates = skills
+            .into_iter()
+            .map(|skill| {
+                let target = rolls
+                    .iter()
+                    .rev()
+                    .find(|roll| roll.skill_name() == skill && roll.target.is_some())
+                    .and_then(|roll| roll.target);
+                GrowthCandidate { skill, target }
+            })
+            .collect();
+        GrowthChecks { candidates }
+    }
+
+    // ココフォリアのチャットパレットにそのまま貼れる形式
+    // 目標値のわからない技能は出力しない
+    pub fn to_palette(&self) -> String {
+        let mut s = String::new();
+        for candidate in &self.candidates {
+            if let Some(target) = candidate.target {
+                s.push_str(&format!(
+                    "1d100>{} 【{}】成長判定\n",
+                    target, candidate.skill
+                ));
+            }
+        }
+        s
+    }
+}
+
+impl Display for GrowthChecks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.candidates.is_empty() {
+            return writeln!(f, "なし");
+        }
+        let skills = self
+            .candidates
+            .iter()
+            .map(|candidate| match candidate.target {
+                Some(target) => format!("《{}》（{}）", candidate.skill, target),
+                None => format!("《{}》", candidate.skill),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "{}", skills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::palette::{apply_palettes, ChatPalette};
+    use crate::roll::parse_rolls;
+    use std::collections::HashMap;
+
+    fn log(text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "PC1".to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_growth_checks() {
+        let logs = vec![
+            log("CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"),
+            log("CCB<=70 【図書館】 (1D100<=70) ＞ 3 ＞ 決定的成功/スペシャル"),
+            log("CCB<=50 【回避】 (1D100<=50) ＞ 60 ＞ 失敗"),
+            log("CCB<=60 【SAN値チェック】 (1D100<=60) ＞ 20 ＞ 成功"),
+            log("CCB<=65 【アイデア】 (1D100<=65) ＞ 20 ＞ 成功"),
+            log("CCB<=80 (1D100<=80) ＞ 20 ＞ 成功"),
+            log("CCB<=35 【目星+10】 (1D100<=35) ＞ 30 ＞ 成功"),
+            log("CCB<=30 【目星】 (1D100<=30) ＞ 90 ＞ 失敗"),
+        ];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let growth = GrowthChecks::new(&rolls);
+
+        let skills: Vec<&str> = growth.candidates.iter().map(|c| c.skill.as_str()).collect();
+        assert_eq!(skills, vec!["図書館", "目星"]);
+        assert_eq!(growth.candidates[1].target, Some(30));
+        assert_eq!(growth.to_string(), "《図書館》（70）, 《目星》（30）\n");
+        assert_eq!(
+            growth.to_palette(),
+            "1d100>70 【図書館】成長判定\n1d100>30 【目星】成長判定\n"
+        );
+    }
+
+    #[test]
+    fn test_no_growth_checks() {
+        let logs = [log("CCB<=50 【回避】 (1D100<=50) ＞ 60 ＞ 失敗")];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let growth = GrowthChecks::new(&rolls);
+        assert_eq!(growth.to_string(), "なし\n");
+        assert_eq!(growth.to_palette(), "");
+    }
+
+    #[test]
+    fn test_growth_checks_with_combination() {
+        let logs = [log(
+            "CBRB(50,60) 【目星,聞き耳】 (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功",
+        )];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let growth = GrowthChecks::new(&rolls);
+        assert_eq!(growth.to_string(), "《聞き耳》（60）\n");
+    }
+
+    #[test]
+    fn test_growth_checks_with_specialized_skills() {
+        let logs = [
+            log("CCB<=60 【芸術(絵画)】 (1D100<=60) ＞ 20 ＞ 成功"),
+            log("CCB<=40 【芸術(歌唱)】 (1D100<=40) ＞ 30 ＞ 成功"),
+        ];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let growth = GrowthChecks::new(&rolls);
+        assert_eq!(
+            growth.to_string(),
+            "《芸術(歌唱)》（40）, 《芸術(絵画)》（60）\n"
+        );
+        assert_eq!(
+            growth.to_palette(),
+            "1d100>40 【芸術(歌唱)】成長判定\n1d100>60 【芸術(絵画)】成長判定\n"
+        );
+    }
+
+    #[test]
+    fn test_no_growth_checks_for_resistance() {
+        let logs = [log("RESB(12-10) (1d100<=60) ＞ 45 ＞ 成功")];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let growth = GrowthChecks::new(&rolls);
+        assert_eq!(growth.to_string(), "なし\n");
+    }
+
+    #[test]
+    fn test_growth_checks_with_palette() {
+        let logs = [log("CCB<=75 (1D100<=75) ＞ 10 ＞ 成功")];
+        let mut rolls = parse_rolls(&logs);
+        let palettes = HashMap::from([(
+            "PC1".to_string(),
+            ChatPalette::parse("CCB<=75 【目星】", &HashMap::new()),
+        )]);
+        apply_palettes(&mut rolls, &palettes);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let growth = GrowthChecks::new(&rolls);
+        assert_eq!(growth.to_string(), "《目星》（75）\n");
+    }
+}
@@ -1,16 +1,80 @@
+use crate::alias::NameResolver;
+use crate::awards::Awards;
+use crate::choice::RandomResult;
+use crate::config::Config;
+use crate::consistency::Inconsistency;
+use crate::detect::Detection;
+use crate::dice::DiceSummary;
+use crate::dx3::Dx3Summary;
+use crate::emoklore::EmokloreSummary;
+use crate::fairness::Fairness;
+use crate::growth::GrowthChecks;
 use crate::log::Log;
 use crate::log_summary::LogSummary;
+use crate::luck::Luck;
+use crate::matrix::SkillMatrix;
+use crate::options::{Options, DEFAULT_CONFIG_PATH};
+use crate::palette::ChatPalette;
+use crate::player::PlayerSummary;
+use crate::reroll::RerollSuspect;
+use crate::role::RoleMap;
+use crate::roll::Roll;
+use crate::secret::SecretReport;
+use crate::sheet::{CharacterSheet, SheetMismatch};
+use crate::shinobigami::SceneSummary;
+use crate::streak::Streaks;
+use crate::sw25::Sw25Summary;
+use crate::system::GameSystem;
+use crate::verify::{CriticalRule, HouseRuleSummary, ResultMismatch};
 use error::MyError;
 use scraper::{Html, Selector};
 use std::collections::{HashMap, HashSet};
 use std::env::Args;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 
+pub mod alias;
+pub mod awards;
+pub mod choice;
+pub mod config;
+pub mod consistency;
+pub mod detect;
+pub mod dice;
+pub mod dx3;
+pub mod emoklore;
 pub mod error;
+pub mod fairness;
+pub mod growth;
 pub mod log;
 pub mod log_summary;
+pub mod luck;
+pub mod matrix;
+pub mod options;
+pub mod palette;
+pub mod player;
+pub mod reroll;
+pub mod role;
+pub mod roll;
+pub mod secret;
+pub mod sheet;
+pub mod shinobigami;
+pub mod stats;
+pub mod streak;
+pub mod sw25;
+pub mod system;
+pub mod verify;
 
+pub fn get_config(options: &Options) -> Result<Config, MyError> {
+    match &options.config_path {
+        Some(path) => Config::load(path),
+        // 指定がなければカレントディレクトリの設定ファイルを探す
+        None if Path::new(DEFAULT_CONFIG_PATH).exists() => Config::load(DEFAULT_CONFIG_PATH),
+        None => Ok(Config::default()),
+    }
+}
+
+#[allow(clippy::unnecessary_lazy_evaluations)]
 pub fn get_logs(mut args: Args) -> Result<Vec<Log>, MyError> {
     let default = "data/log5.html".to_string();
 
@@ -22,7 +86,21 @@ pub fn get_logs(mut args: Args) -> Result<Vec<Log>, MyError> {
         default
     };
 
-    let mut file = File::open(&filename)?;
+    get_logs_from_file(&filename)
+}
+
+// 複数のログを順につなげる
+// キャンペーンの全セッションをまとめて集計するときに使う
+pub fn get_logs_from_files(filenames: &[String]) -> Result<Vec<Log>, MyError> {
+    let mut logs = Vec::new();
+    for filename in filenames {
+        logs.extend(get_logs_from_file(filename)?);
+    }
+    Ok(logs)
+}
+
+pub fn get_logs_from_file(filename: &str) -> Result<Vec<Log>, MyError> {
+    let mut file = File::open(filename)?;
     let mut html = String::new();
     file.read_to_string(&mut html)?;
 
@@ -40,34 +118,134 @@ pub fn get_logs(mut args: Args) -> Result<Vec<Log>, MyError> {
     // 一つのpタグに一つのチャットが入っている
     for p_tag in p_tags {
         let span_tags = p_tag.select(&span_selector);
-        let log = Log::new(span_tags)?;
+        let mut log = Log::new(span_tags)?;
+        log.color = Log::get_color(p_tag);
 
         // ---start---以前は無視する
         if log.texts.len() == 1 && log.texts[0] == "---start---" {
             logs = Vec::new();
         }
 
-        logs.push(log)
+        logs.push(log.expand_repeats())
     }
     Ok(logs)
 }
 
-pub fn get_log_summary(logs: &Vec<Log>) -> LogSummary {
+// キャラクターシートを読み込む
+// 名前はログと同じ規則でまとめる
+pub fn get_sheets(
+    filenames: &[String],
+    config: &Config,
+) -> Result<HashMap<String, CharacterSheet>, MyError> {
+    let resolver = NameResolver::new(config);
+    let mut sheets = HashMap::new();
+    for filename in filenames {
+        let mut sheet = CharacterSheet::load(filename)?;
+        sheet.name = resolver.resolve(&sheet.name);
+        sheets.insert(sheet.name.clone(), sheet);
+    }
+    Ok(sheets)
+}
+
+// キャラクターごとのチャットパレット
+// シートのパレットに--paletteで指定したファイルの内容を足す
+pub fn get_palettes(
+    palette_paths: &[(String, String)],
+    sheets: &HashMap<String, CharacterSheet>,
+    config: &Config,
+) -> Result<HashMap<String, ChatPalette>, MyError> {
+    let resolver = NameResolver::new(config);
+    let mut palettes: HashMap<String, ChatPalette> = sheets
+        .iter()
+        .map(|(name, sheet)| (name.clone(), sheet.palette.clone()))
+        .collect();
+    for (name, filename) in palette_paths {
+        let name = resolver.resolve(name);
+        let variables = sheets
+            .get(&name)
+            .map(|sheet| sheet.variables())
+            .unwrap_or_default();
+        let palette = ChatPalette::load(filename, &variables)?;
+        palettes.entry(name).or_default().extend(palette);
+    }
+    Ok(palettes)
+}
+
+// 集計に使うゲームシステム
+// コマンドライン・設定ファイルの指定を優先し、なければログから判定する
+pub fn get_system(
+    options: &Options,
+    config: &Config,
+    logs: &[Log],
+) -> (GameSystem, Option<Detection>) {
+    match options.system.or(config.system) {
+        Some(system) => (system, None),
+        None => {
+            let detection = Detection::new(logs);
+            (detection.system, Some(detection))
+        }
+    }
+}
+
+// 表記ゆれのある発言者名をまとめる
+// get_pc_summary_with_rolesなどで集計する前に呼ぶ
+pub fn merge_names(logs: &mut [Log], config: &Config) {
+    NameResolver::new(config).apply(logs);
+}
+
+// シークレットダイスを取り除く
+// 参加者に見せる集計を作るときに使う
+pub fn hide_secrets(logs: &[Log]) -> Vec<Log> {
+    secret::hide_secrets(logs)
+}
+
+pub fn get_secret_report(logs: &[Log]) -> SecretReport<'_> {
+    SecretReport::new(logs)
+}
+
+pub fn get_log_summary(logs: &[Log]) -> LogSummary<'_> {
     LogSummary::new(logs.iter().collect())
 }
 
-pub fn get_pc_summary(logs: &Vec<Log>) -> HashMap<String, LogSummary> {
-    let names: HashSet<_> = logs.iter().map(|log| log.name.clone()).collect();
+pub fn get_roles(logs: &[Log], config: &Config) -> RoleMap {
+    RoleMap::detect(logs, config)
+}
+
+// KP・NPCとして扱う発言者はまとめて一つの集計にする
+pub fn get_kp_summary<'a>(logs: &'a [Log], roles: &RoleMap) -> LogSummary<'a> {
+    LogSummary::new(logs.iter().filter(|log| !roles.is_pc(&log.name)).collect())
+}
+
+// 発言者ごとの集計
+// KP・NPCの区別やチャットパレットを使うときはget_pc_summary_with_rolesを使う
+#[allow(clippy::ptr_arg)]
+pub fn get_pc_summary(logs: &Vec<Log>) -> HashMap<String, LogSummary<'_>> {
+    get_pc_summary_with_roles(logs, &RoleMap::default(), &HashMap::new())
+}
+
+// PCごとの集計
+// 【】のないロールはチャットパレットから技能名を補う
+pub fn get_pc_summary_with_roles<'a>(
+    logs: &'a [Log],
+    roles: &RoleMap,
+    palettes: &HashMap<String, ChatPalette>,
+) -> HashMap<String, LogSummary<'a>> {
+    let names: HashSet<_> = logs
+        .iter()
+        .filter(|log| roles.is_pc(&log.name))
+        .map(|log| log.name.clone())
+        .collect();
     let mut map = HashMap::new();
     for name in names {
         let logs: Vec<&Log> = logs.iter().filter(|log| log.name == name).collect();
-        let log_summary = LogSummary::new(logs);
+        let log_summary = LogSummary::new(logs).with_palette(palettes.get(&name));
 
         let mut count = 0;
-        count += log_summary.successes.iter().count();
-        count += log_summary.failures.iter().count();
-        count += log_summary.criticals.iter().count();
-        count += log_summary.fumbles.iter().count();
+        count += log_summary.successes.len();
+        count += log_summary.failures.len();
+        count += log_summary.criticals.len();
+        count += log_summary.fumbles.len();
+        count += log_summary.resistances.len();
         if count == 0 {
             continue;
         }
@@ -75,3 +253,264 @@ pub fn get_pc_summary(logs: &Vec<Log>) -> HashMap<String, LogSummary> {
     }
     map
 }
+
+pub fn get_player_summary<'a>(
+    logs: &'a [Log],
+    config: &Config,
+) -> HashMap<String, PlayerSummary<'a>> {
+    player::summarize_by_player(logs, config)
+}
+
+// 判定ロールを取り出す
+// Roll::indexはlogs内の位置になる
+// 【】のないロールはチャットパレットから技能名を補う
+pub fn get_rolls<'a>(logs: &'a [Log], palettes: &HashMap<String, ChatPalette>) -> Vec<Roll<'a>> {
+    let mut rolls = roll::parse_rolls(logs);
+    palette::apply_palettes(&mut rolls, palettes);
+    rolls
+}
+
+// PCごとに判定ロールを分ける
+pub fn get_pc_rolls<'r, 'a>(
+    rolls: &'r [Roll<'a>],
+    roles: &RoleMap,
+) -> HashMap<String, Vec<&'r Roll<'a>>> {
+    let mut map: HashMap<String, Vec<&Roll>> = HashMap::new();
+    for roll in rolls {
+        if roles.is_pc(roll.name()) {
+            map.entry(roll.name().to_string()).or_default().push(roll);
+        }
+    }
+    map
+}
+
+pub fn get_pc_fairness(
+    rolls: &[Roll],
+    roles: &RoleMap,
+    system: GameSystem,
+    config: &Config,
+) -> HashMap<String, Fairness> {
+    get_pc_rolls(rolls, roles)
+        .into_iter()
+        .map(|(name, rolls)| (name, Fairness::new(&rolls, system, config.critical_rule)))
+        .collect()
+}
+
+// KP・NPCも含めた卓全体の出目
+pub fn get_table_fairness(rolls: &[Roll], system: GameSystem, config: &Config) -> Fairness {
+    Fairness::new(
+        &rolls.iter().collect::<Vec<_>>(),
+        system,
+        config.critical_rule,
+    )
+}
+
+pub fn get_pc_luck(rolls: &[Roll], roles: &RoleMap, system: GameSystem) -> HashMap<String, Luck> {
+    get_pc_rolls(rolls, roles)
+        .into_iter()
+        .map(|(name, rolls)| (name, Luck::new(&rolls, system)))
+        .collect()
+}
+
+// ハウスルールで判定し直したクリティカル・ファンブルの数
+pub fn get_pc_house_rule(
+    rolls: &[Roll],
+    roles: &RoleMap,
+    rule: CriticalRule,
+) -> HashMap<String, HouseRuleSummary> {
+    get_pc_rolls(rolls, roles)
+        .into_iter()
+        .map(|(name, rolls)| (name, HouseRuleSummary::new(&rolls, rule)))
+        .collect()
+}
+
+pub fn get_awards(logs: &[Log], rolls: &[Roll], roles: &RoleMap, config: &Config) -> Awards {
+    Awards::new(logs, rolls, roles, &NameResolver::new(config))
+}
+
+pub fn get_pc_streaks(rolls: &[Roll], roles: &RoleMap) -> HashMap<String, Streaks> {
+    get_pc_rolls(rolls, roles)
+        .into_iter()
+        .map(|(name, rolls)| (name, Streaks::new(&rolls)))
+        .collect()
+}
+
+pub fn get_skill_matrix(
+    rolls: &[Roll],
+    roles: &RoleMap,
+    sheets: &HashMap<String, CharacterSheet>,
+) -> SkillMatrix {
+    let mut matrix = SkillMatrix::new(&get_pc_rolls(rolls, roles));
+    matrix.set_ratings(sheets);
+    matrix
+}
+
+// CoC6の成長判定の候補
+pub fn get_pc_growth_checks(rolls: &[Roll], roles: &RoleMap) -> HashMap<String, GrowthChecks> {
+    get_pc_rolls(rolls, roles)
+        .into_iter()
+        .map(|(name, rolls)| (name, GrowthChecks::new(&rolls)))
+        .collect()
+}
+
+// 同じ技能で目標値が食い違っているロール
+pub fn get_inconsistencies(rolls: &[Roll], roles: &RoleMap) -> Vec<Inconsistency> {
+    consistency::find_inconsistencies(&get_pc_rolls(rolls, roles))
+}
+
+// 出目と目標値から求めた結果とログの結果が食い違っているロール
+// KP・NPCのロールも含める
+pub fn get_result_mismatches<'a>(
+    rolls: &[Roll<'a>],
+    system: GameSystem,
+    config: &Config,
+) -> Vec<ResultMismatch<'a>> {
+    verify::find_result_mismatches(rolls, system, config.critical_rule)
+}
+
+// 失敗直後に同じ技能を振り直した疑いのあるロール
+pub fn get_reroll_suspects<'a>(
+    rolls: &[Roll<'a>],
+    roles: &RoleMap,
+    window: usize,
+) -> Vec<RerollSuspect<'a>> {
+    reroll::find_rerolls(&get_pc_rolls(rolls, roles), window)
+}
+
+// キャラクターシートの技能値と違う目標値で振ったロール
+pub fn get_sheet_mismatches<'a>(
+    rolls: &[Roll<'a>],
+    roles: &RoleMap,
+    sheets: &HashMap<String, CharacterSheet>,
+) -> Vec<SheetMismatch<'a>> {
+    sheet::find_sheet_mismatches(sheets, &get_pc_rolls(rolls, roles))
+}
+
+// システムごとのロールをPCごとに分ける
+fn group_by_pc<'r, T>(
+    items: &'r [T],
+    roles: &RoleMap,
+    name: impl Fn(&T) -> &str,
+) -> HashMap<String, Vec<&'r T>> {
+    let mut map: HashMap<String, Vec<&T>> = HashMap::new();
+    for item in items {
+        if roles.is_pc(name(item)) {
+            map.entry(name(item).to_string()).or_default().push(item);
+        }
+    }
+    map
+}
+
+// KP・NPCのものだけを取り出す
+fn filter_non_pc<'r, T>(items: &'r [T], roles: &RoleMap, name: impl Fn(&T) -> &str) -> Vec<&'r T> {
+    items
+        .iter()
+        .filter(|item| !roles.is_pc(name(item)))
+        .collect()
+}
+
+// choiceやランダム表の結果（ログの順）
+pub fn get_random_results(logs: &[Log]) -> Vec<RandomResult<'_>> {
+    choice::parse_random_results(logs)
+}
+
+pub fn get_pc_random_results<'r, 'a>(
+    results: &'r [RandomResult<'a>],
+    roles: &RoleMap,
+) -> HashMap<String, Vec<&'r RandomResult<'a>>> {
+    group_by_pc(results, roles, |result| result.name())
+}
+
+// ダメージなど成功・失敗のないダイスの合計をPCごとに集計する
+pub fn get_pc_dice(logs: &[Log], roles: &RoleMap) -> HashMap<String, DiceSummary> {
+    let rolls = dice::parse_dice_rolls(logs);
+    group_by_pc(&rolls, roles, |roll| &roll.log.name)
+        .into_iter()
+        .map(|(name, rolls)| (name, DiceSummary::new(&rolls)))
+        .collect()
+}
+
+// エモクロアの成功数をPCごとに集計する
+pub fn get_pc_emoklore(logs: &[Log], roles: &RoleMap) -> HashMap<String, EmokloreSummary> {
+    let rolls = emoklore::parse_emoklore_rolls(logs);
+    group_by_pc(&rolls, roles, |roll| &roll.log.name)
+        .into_iter()
+        .map(|(name, rolls)| (name, EmokloreSummary::new(&rolls)))
+        .collect()
+}
+
+// KP・NPCのエモクロアの判定をまとめて集計する
+pub fn get_kp_emoklore(logs: &[Log], roles: &RoleMap) -> EmokloreSummary {
+    let rolls = emoklore::parse_emoklore_rolls(logs);
+    EmokloreSummary::new(&filter_non_pc(&rolls, roles, |roll| &roll.log.name))
+}
+
+// SW2.5の判定とダメージをPCごとに集計する
+pub fn get_pc_sw25(logs: &[Log], roles: &RoleMap) -> HashMap<String, Sw25Summary> {
+    let (checks, damages) = sw25::parse_sw25_rolls(logs);
+    let checks_by_name = group_by_pc(&checks, roles, |check| &check.log.name);
+    let damages_by_name = group_by_pc(&damages, roles, |damage| &damage.log.name);
+
+    let names: HashSet<&String> = checks_by_name
+        .keys()
+        .chain(damages_by_name.keys())
+        .collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let checks = checks_by_name.get(name).map_or(&[][..], |c| c.as_slice());
+            let damages = damages_by_name.get(name).map_or(&[][..], |d| d.as_slice());
+            (name.clone(), Sw25Summary::new(checks, damages))
+        })
+        .collect()
+}
+
+// KP・NPCのSW2.5の判定とダメージをまとめて集計する
+pub fn get_kp_sw25(logs: &[Log], roles: &RoleMap) -> Sw25Summary {
+    let (checks, damages) = sw25::parse_sw25_rolls(logs);
+    Sw25Summary::new(
+        &filter_non_pc(&checks, roles, |check| &check.log.name),
+        &filter_non_pc(&damages, roles, |damage| &damage.log.name),
+    )
+}
+
+// DX3の達成値とクリティカルをPCごとに集計する
+pub fn get_pc_dx3(logs: &[Log], roles: &RoleMap) -> HashMap<String, Dx3Summary> {
+    let rolls = dx3::parse_dx3_rolls(logs);
+    group_by_pc(&rolls, roles, |roll| &roll.log.name)
+        .into_iter()
+        .map(|(name, rolls)| (name, Dx3Summary::new(&rolls)))
+        .collect()
+}
+
+// KP・NPCのDX3の達成値とクリティカルをまとめて集計する
+pub fn get_kp_dx3(logs: &[Log], roles: &RoleMap) -> Dx3Summary {
+    let rolls = dx3::parse_dx3_rolls(logs);
+    Dx3Summary::new(&filter_non_pc(&rolls, roles, |roll| &roll.log.name))
+}
+
+// シノビガミ・インセインの判定と表の結果をPCごとに集計する
+pub fn get_pc_scene(logs: &[Log], roles: &RoleMap) -> HashMap<String, SceneSummary> {
+    let (checks, tables) = shinobigami::parse_scene_rolls(logs);
+    let checks_by_name = group_by_pc(&checks, roles, |check| &check.log.name);
+    let tables_by_name = group_by_pc(&tables, roles, |table| &table.log.name);
+
+    let names: HashSet<&String> = checks_by_name.keys().chain(tables_by_name.keys()).collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let checks = checks_by_name.get(name).map_or(&[][..], |c| c.as_slice());
+            let tables = tables_by_name.get(name).map_or(&[][..], |t| t.as_slice());
+            (name.clone(), SceneSummary::new(checks, tables))
+        })
+        .collect()
+}
+
+// KP・NPCのシノビガミ・インセインの判定と表の結果をまとめて集計する
+pub fn get_kp_scene(logs: &[Log], roles: &RoleMap) -> SceneSummary {
+    let (checks, tables) = shinobigami::parse_scene_rolls(logs);
+    SceneSummary::new(
+        &filter_non_pc(&checks, roles, |check| &check.log.name),
+        &filter_non_pc(&tables, roles, |table| &table.log.name),
+    )
+}
@@ -1,14 +1,17 @@
 use crate::error::{MyError, ParseError};
+use crate::roll::expand_repeated_roll;
 use scraper::element_ref::Select;
 use scraper::ElementRef;
 use std::fmt;
 use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Log {
     pub tab: String,
     pub name: String,
     pub texts: Vec<String>,
+    // 発言の文字色（キャラクターごとに設定される）
+    pub color: String,
 }
 
 impl Log {
@@ -43,7 +46,36 @@ impl Log {
             }
         };
 
-        Ok(Log { tab, name, texts })
+        Ok(Log {
+            tab,
+            name,
+            texts,
+            color: String::new(),
+        })
+    }
+
+    // 繰り返しロールは1回ごとのテキストに分ける
+    // 件数がずれないよう、チャット自体は一つのまま残す
+    // 結果が複数行に分かれていることがあるので、つなげてから分ける
+    pub fn expand_repeats(mut self) -> Log {
+        if let Some(texts) = expand_repeated_roll(&self.texts.join(" ")) {
+            self.texts = texts;
+        }
+        self
+    }
+
+    // pタグのstyle属性から文字色を取り出す
+    // 例：style="color:#888888;"
+    pub fn get_color(p_tag: ElementRef) -> String {
+        let Some(style) = p_tag.value().attr("style") else {
+            return String::new();
+        };
+        style
+            .split(';')
+            .filter_map(|declaration| declaration.split_once(':'))
+            .find(|(property, _)| property.trim() == "color")
+            .map(|(_, value)| value.trim().to_lowercase())
+            .unwrap_or_default()
     }
 
     fn validate_tab(tab: String) -> Result<String, ParseError> {
@@ -1,7 +1,10 @@
 use crate::log::Log;
+use crate::palette::{insert_label, ChatPalette};
+use crate::roll::{combination_results, is_resistance_roll, parse_target, RollResult};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
+#[allow(clippy::manual_map)]
 fn extract_skills_from_log_text(text: &str) -> Option<String> {
     match text.find('＞') { // Using full-width greater than sign
         Some(index) => {
@@ -11,6 +14,7 @@ fn extract_skills_from_log_text(text: &str) -> Option<String> {
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 fn extract_skills_for_logs<'a>(logs: &[&'a Log]) -> HashMap<String, usize> {
     let mut skills_map: HashMap<String, usize> = HashMap::new();
     for log in logs {
@@ -23,6 +27,30 @@ fn extract_skills_for_logs<'a>(logs: &[&'a Log]) -> HashMap<String, usize> {
     skills_map
 }
 
+// 【】のないコマンドにチャットパレットの技能名を補う
+fn label_skills(skills_map: HashMap<String, usize>, palette: &ChatPalette) -> HashMap<String, usize> {
+    let mut labeled: HashMap<String, usize> = HashMap::new();
+    for (command, count) in skills_map {
+        let command = match palette.skill_for(&command, parse_target(&command)) {
+            Some(skill) if !command.contains('【') => insert_label(&command, &skill),
+            _ => command,
+        };
+        *labeled.entry(command).or_insert(0) += count;
+    }
+    labeled
+}
+
+// UserChoiceの順（成功・失敗・クリティカル・ファンブル）の結果が書かれたテキストか
+fn is_result_text(index: usize, text: &str) -> bool {
+    match index {
+        0 => (text.contains("成功") || text.contains("スペシャル")) && !text.contains("決定的成功"),
+        1 => text.contains("失敗") && !text.contains("致命的失敗"),
+        2 => text.contains("決定的成功"),
+        3 => text.contains("致命的失敗"),
+        _ => false,
+    }
+}
+
 pub enum UserChoice {
     Success,
     Failure,
@@ -56,48 +84,115 @@ pub struct LogSummary<'a> {
     pub failures: Vec<&'a Log>,
     pub criticals: Vec<&'a Log>,
     pub fumbles: Vec<&'a Log>,
+    // 対抗ロール（RESB）とその結果
+    pub resistances: Vec<(&'a Log, RollResult)>,
+    // 技能名を補うためのチャットパレット
+    pub palette: Option<ChatPalette>,
 }
 
 impl<'a> LogSummary<'a> {
     pub fn new(logs: Vec<&'a Log>) -> LogSummary<'a> {
-        let successes: Vec<_> = logs
-            .iter()
-            .filter(|log| {
-                log.texts.iter().any(|text| {
-                    (text.contains("成功") || text.contains("スペシャル"))
-                        && !text.contains("決定的成功")
-                })
-            })
-            .map(|log| *log)
-            .collect();
-
-        let failures: Vec<_> = logs
-            .iter()
-            .filter(|log| {
-                log.texts
-                    .iter()
-                    .any(|text| text.contains("失敗") && !text.contains("致命的失敗"))
-            })
-            .map(|log| *log)
-            .collect();
-
-        let criticals: Vec<_> = logs
-            .iter()
-            .filter(|log| log.texts.iter().any(|text| text.contains("決定的成功")))
-            .map(|log| *log)
-            .collect();
-
-        let fumbles: Vec<_> = logs
-            .iter()
-            .filter(|log| log.texts.iter().any(|text| text.contains("致命的失敗")))
-            .map(|log| *log)
-            .collect();
+        let mut summary = LogSummary {
+            successes: Vec::new(),
+            failures: Vec::new(),
+            criticals: Vec::new(),
+            fumbles: Vec::new(),
+            resistances: Vec::new(),
+            palette: None,
+        };
 
-        LogSummary {
-            successes,
-            failures,
-            criticals,
-            fumbles,
+        for log in logs {
+            let mut is_success = false;
+            let mut is_failure = false;
+            let mut is_critical = false;
+            let mut is_fumble = false;
+            for text in &log.texts {
+                // 対抗ロールは別に数える
+                if is_resistance_roll(text) {
+                    if let Some(result) = RollResult::from_text(text.rsplit('＞').next().unwrap_or("")) {
+                        summary.resistances.push((log, result));
+                    }
+                    continue;
+                }
+                // 組み合わせロールは技能ごとの結果をそれぞれ数える
+                if let Some(results) = combination_results(text) {
+                    for result in results {
+                        match result {
+                            RollResult::Critical => summary.criticals.push(log),
+                            RollResult::Success => summary.successes.push(log),
+                            RollResult::Failure => summary.failures.push(log),
+                            RollResult::Fumble => summary.fumbles.push(log),
+                        }
+                    }
+                    continue;
+                }
+
+                // 繰り返しロールは一つのチャットに結果が並ぶので、ロールごとに数える
+                if text.contains('＞') {
+                    for index in 0..4 {
+                        if is_result_text(index, text) {
+                            summary.logs_mut(index).push(log);
+                        }
+                    }
+                    continue;
+                }
+
+                is_success |= is_result_text(0, text);
+                is_failure |= is_result_text(1, text);
+                is_critical |= is_result_text(2, text);
+                is_fumble |= is_result_text(3, text);
+            }
+
+            if is_success {
+                summary.successes.push(log);
+            }
+            if is_failure {
+                summary.failures.push(log);
+            }
+            if is_critical {
+                summary.criticals.push(log);
+            }
+            if is_fumble {
+                summary.fumbles.push(log);
+            }
+        }
+        summary
+    }
+
+    pub fn with_palette(mut self, palette: Option<&ChatPalette>) -> LogSummary<'a> {
+        self.palette = palette.cloned();
+        self
+    }
+
+    fn logs_mut(&mut self, index: usize) -> &mut Vec<&'a Log> {
+        match index {
+            0 => &mut self.successes,
+            1 => &mut self.failures,
+            2 => &mut self.criticals,
+            _ => &mut self.fumbles,
+        }
+    }
+
+    // 繰り返しロールや組み合わせロールは同じチャットが何度も入っているので、
+    // k回目に出てきたチャットからはk番目に結果の合うテキストを数える
+    fn extract_skills(&self, logs: &[&Log], index: usize) -> HashMap<String, usize> {
+        let mut skills_map: HashMap<String, usize> = HashMap::new();
+        for (i, log) in logs.iter().enumerate() {
+            let texts: Vec<&String> = log.texts.iter().filter(|text| text.contains('＞') && is_result_text(index, text)).collect();
+            if texts.is_empty() {
+                for (skill_name, count) in extract_skills_for_logs(&[*log]) {
+                    *skills_map.entry(skill_name).or_insert(0) += count;
+                }
+                continue;
+            }
+            let k = logs[..i].iter().filter(|other| std::ptr::eq(**other, *log)).count();
+            if let Some(skill_name) = extract_skills_from_log_text(texts[k % texts.len()]) {
+                *skills_map.entry(skill_name).or_insert(0) += 1;
+            }
+        }
+        match &self.palette {
+            Some(palette) => label_skills(skills_map, palette),
+            None => skills_map,
         }
     }
 
@@ -128,6 +223,10 @@ impl<'a> LogSummary<'a> {
         s.push_str(&format!("通常失敗：{}\n", self.failures.len()));
         s.push_str(&format!("クリティカル：{}\n", self.criticals.len()));
         s.push_str(&format!("ファンブル：{}\n", self.fumbles.len()));
+        if !self.resistances.is_empty() {
+            let successes = self.resistances.iter().filter(|(_, result)| result.is_success()).count();
+            s.push_str(&format!("対抗ロール：{}（成功{}）\n", self.resistances.len(), successes));
+        }
 
         if let Some(index) = chosen_result_index {
             let (log_type_name, logs_to_process) = match index {
@@ -138,7 +237,7 @@ impl<'a> LogSummary<'a> {
                 _ => return s, // Invalid index, return current summary
             };
 
-            let skills_map = extract_skills_for_logs(logs_to_process);
+            let skills_map = self.extract_skills(logs_to_process, index);
 
             if skills_map.is_empty() {
                 s.push_str(&format!("{}した技能: なし\n", log_type_name));
@@ -167,7 +266,7 @@ impl<'a> LogSummary<'a> {
             UserChoice::Fumble => &self.fumbles,
         };
 
-        let skills_map = extract_skills_for_logs(logs_to_process);
+        let skills_map = self.extract_skills(logs_to_process, chosen_result_index);
         let result_type_display_string = user_choice.to_display_string();
 
         if skills_map.is_empty() {
@@ -253,6 +352,7 @@ mod tests {
             name: "PC1".to_string(),
             // These texts do not contain '＞'
             texts: vec!["イオリ (いおり) : 1d10 (1D10) = 8".to_string(), "CCB<=80 (1D100<=80) = 50 = 成功".to_string()],
+            color: String::new(),
         };
         let logs_slice = vec![&log1];
         let skills = extract_skills_for_logs(&logs_slice);
@@ -265,6 +365,7 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功".to_string()],
+            color: String::new(),
         };
         let logs_slice = vec![&log1];
         let skills = extract_skills_for_logs(&logs_slice);
@@ -279,6 +380,7 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=60 【攻撃】 (1D100<=60) ＞ 30 ＞ 成功".to_string()], // Skill: "CCB<=60 【攻撃】 (1D100<=60)"
+            color: String::new(),
         };
         let log2 = Log {
             tab: "メイン".to_string(),
@@ -287,6 +389,7 @@ mod tests {
                 "CCB<=50 【回避】 (1D100<=50) ＞ 55 ＞ 失敗".to_string(), // Skill: "CCB<=50 【回避】 (1D100<=50)"
                 "何か【攻撃】 (1D100<=60) ＞ 5 ＞ 決定的成功".to_string()  // Skill: "何か【攻撃】 (1D100<=60)"
             ],
+            color: String::new(),
         };
         let log3 = Log {
             tab: "メイン".to_string(),
@@ -295,6 +398,7 @@ mod tests {
                 "CCB<=70 【応急手当】 (1D100<=70) ＞ 20 ＞ 成功".to_string(), // Skill: "CCB<=70 【応急手当】 (1D100<=70)"
                 "そして【攻撃】 (1D100<=60) ＞ 98 ＞ 致命的失敗".to_string() // Skill: "そして【攻撃】 (1D100<=60)"
             ],
+            color: String::new(),
         };
         let logs_slice = vec![&log1, &log2, &log3];
         let skills = extract_skills_for_logs(&logs_slice);
@@ -319,6 +423,7 @@ mod tests {
                 "【知略】(1d100<=60) ＞ 30 ＞ 成功".to_string(),
                 "【知略】(1d100<=60) ＞ 40 ＞ 成功".to_string()
             ],
+            color: String::new(),
         };
         let logs_slice = vec![&log1];
         let skills = extract_skills_for_logs(&logs_slice);
@@ -339,6 +444,8 @@ mod tests {
             failures,
             criticals,
             fumbles,
+            resistances: vec![],
+            palette: None,
         }
     }
 
@@ -360,16 +467,19 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=5 【目星】 (1D100<=5) ＞ 3 ＞ 決定的成功".to_string()], // Skill: "CCB<=5 【目星】 (1D100<=5)"
+            color: String::new(),
         };
         let crit_log2 = Log {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["部屋の隅で【聞き耳】 (1D100<=71) ＞ 1 ＞ スペシャル！".to_string()], // Skill: "部屋の隅で【聞き耳】 (1D100<=71)"
+            color: String::new(),
         };
          let crit_log3 = Log { // Same skill as log1, but different dice string
             tab: "メイン".to_string(),
             name: "PC2".to_string(),
             texts: vec!["CCB<=10 【目星】 (1D100<=10) ＞ 1 ＞ 決定的成功！！！！".to_string()], // Skill: "CCB<=10 【目星】 (1D100<=10)"
+            color: String::new(),
         };
         let summary = create_test_summary(vec![], vec![], vec![&crit_log1, &crit_log2, &crit_log3], vec![]);
         let output = summary.format_with_skills(Some(UserChoice::Critical as usize));
@@ -390,11 +500,13 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["1d100<=48 【SAN値チェック】 (1D100<=48) ＞ 22 ＞ 成功".to_string()], // Skill: "1d100<=48 【SAN値チェック】 (1D100<=48)"
+            color: String::new(),
         };
          let success_log2 = Log {
             tab: "メイン".to_string(),
             name: "PC2".to_string(),
             texts: vec!["CCB<=80 (1D100<=80) ＞ 50 ＞ 成功".to_string()], // Skill: "CCB<=80 (1D100<=80)"
+            color: String::new(),
         };
         let summary = create_test_summary(vec![&success_log1, &success_log2], vec![], vec![], vec![]);
 
@@ -419,6 +531,7 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=71 【聞き耳】 (1D100<=71) ＞ 96 ＞ 致命的失敗".to_string()], // Skill: "CCB<=71 【聞き耳】 (1D100<=71)"
+            color: String::new(),
         };
         let summary = create_test_summary(vec![], vec![], vec![], vec![&fumble_log]);
         let output = summary.format_with_skills(Some(UserChoice::Fumble as usize));
@@ -434,6 +547,7 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=80 (1D100<=80) = 100 = 致命的失敗".to_string()], // No '＞'
+            color: String::new(),
         };
         let summary = create_test_summary(vec![], vec![], vec![], vec![&fumble_log_no_skill]);
         let output = summary.format_with_skills(Some(UserChoice::Fumble as usize));
@@ -456,11 +570,13 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=25 【目星】 (1D100<=25) ＞ 1 ＞ 決定的成功".to_string()],
+            color: String::new(),
         };
         let crit_log2 = Log {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=5 【ブラフ】 (1D100<=5) ＞ 1 ＞ スペシャル".to_string()],
+            color: String::new(),
         };
         let summary = create_test_summary(vec![], vec![], vec![&crit_log1, &crit_log2], vec![]);
         let output = summary.format_chosen_skills_only(UserChoice::Critical as usize);
@@ -476,6 +592,7 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=80 (1D100<=80) = 50 = 成功".to_string()], // No '＞'
+            color: String::new(),
         };
         let summary = create_test_summary(vec![&success_log_no_gt], vec![], vec![], vec![]);
         let output = summary.format_chosen_skills_only(UserChoice::Success as usize);
@@ -488,16 +605,19 @@ mod tests {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=71 【応急手当】 (1D100<=71) ＞ 96 ＞ 致命的失敗".to_string()],
+            color: String::new(),
         };
         let fumble2 = Log {
             tab: "メイン".to_string(),
             name: "PC1".to_string(),
             texts: vec!["CCB<=50 【回避】 (1D100<=50) ＞ 100 ＞ 致命的失敗".to_string()],
+            color: String::new(),
         };
          let fumble3 = Log { // Duplicate of fumble2's skill text to test count
             tab: "メイン".to_string(),
             name: "PC2".to_string(),
             texts: vec!["CCB<=50 【回避】 (1D100<=50) ＞ 99 ＞ 致命的失敗".to_string()],
+            color: String::new(),
         };
         let summary = create_test_summary(vec![], vec![], vec![], vec![&fumble1, &fumble2, &fumble3]);
         let output = summary.format_chosen_skills_only(UserChoice::Fumble as usize);
@@ -519,4 +639,72 @@ mod tests {
         let output = summary.format_chosen_skills_only(99); // Invalid index
         assert_eq!(output, ""); // Expects empty string as per current implementation
     }
+
+    #[test]
+    fn test_format_chosen_skills_with_palette() {
+        let log1 = Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["CCB<=75 (1D100<=75) ＞ 10 ＞ 成功".to_string()],
+            color: String::new(),
+        };
+        let log2 = Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["CCB<=75 【目星】 (1D100<=75) ＞ 20 ＞ 成功".to_string()],
+            color: String::new(),
+        };
+        let palette = ChatPalette::parse("CCB<=75 目星", &HashMap::new());
+        let summary = LogSummary::new(vec![&log1, &log2]).with_palette(Some(&palette));
+        let output = summary.format_chosen_skills_only(UserChoice::Success as usize);
+        assert_eq!(output, "  成功した技能: 《CCB<=75 【目星】 (1D100<=75)》（2回）");
+    }
+
+    #[test]
+    fn test_new_with_combination_and_resistance() {
+        let log1 = Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["CBRB(50,60) (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功".to_string()],
+            color: String::new(),
+        };
+        let log2 = Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["CBRB(50,60) (1d100<=50,60) ＞ 20[成功,成功] ＞ 成功".to_string()],
+            color: String::new(),
+        };
+        let log3 = Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["RESB(12-10) (1d100<=60) ＞ 45 ＞ 成功".to_string()],
+            color: String::new(),
+        };
+        let summary = LogSummary::new(vec![&log1, &log2, &log3]);
+        assert_eq!(summary.successes.len(), 3);
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.resistances.len(), 1);
+        assert_eq!(
+            summary.format_with_skills(None),
+            "通常成功：3\n通常失敗：1\nクリティカル：0\nファンブル：0\n対抗ロール：1（成功1）\n"
+        );
+    }
+
+    #[test]
+    fn test_new_with_repeated_roll() {
+        let log = Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["x3 CCB<=50 【幸運】 #1 (1D100<=50) ＞ 70 ＞ 失敗 #2 (1D100<=50) ＞ 20 ＞ 成功 #3 (1D100<=50) ＞ 80 ＞ 失敗".to_string()],
+            color: String::new(),
+        }
+        .expand_repeats();
+        let summary = LogSummary::new(vec![&log]);
+        assert_eq!(summary.successes.len(), 1);
+        assert_eq!(summary.failures.len(), 2);
+        assert_eq!(
+            summary.format_chosen_skills_only(UserChoice::Failure as usize),
+            "  失敗した技能: 《CCB<=50 【幸運】 (1D100<=50)》（2回）"
+        );
+    }
 }
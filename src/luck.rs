@@ -0,0 +1,175 @@
+use crate::roll::{Roll, RollResult};
+use crate::system::GameSystem;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+// 運勢指数
+// 技能値から期待される成功数と実際の成功数を比べる
+pub struct Luck {
+    // 目標値のわかる1D100判定の数
+    pub rolls: usize,
+    pub successes: usize,
+    pub criticals: usize,
+    pub fumbles: usize,
+    pub expected_successes: f64,
+    pub expected_criticals: f64,
+    pub expected_fumbles: f64,
+}
+
+impl Luck {
+    pub fn new(rolls: &[&Roll], system: GameSystem) -> Luck {
+        let mut luck = Luck {
+            rolls: 0,
+            successes: 0,
+            criticals: 0,
+            fumbles: 0,
+            expected_successes: 0.0,
+            expected_criticals: 0.0,
+            expected_fumbles: 0.0,
+        };
+
+        for roll in rolls {
+            let Some(target) = roll.target else {
+                continue;
+            };
+            if !roll.is_d100() {
+                continue;
+            }
+            luck.rolls += 1;
+            luck.expected_successes += system.success_probability(target);
+            if roll.result.is_success() {
+                luck.successes += 1;
+            }
+
+            // 成否は技能ごとに決まるが、クリティカル・ファンブルは出目で決まるので
+            // 組み合わせロールでは一度だけ数える
+            if !roll.is_first_part() {
+                continue;
+            }
+            luck.expected_criticals += system.critical_probability(target);
+            luck.expected_fumbles += system.fumble_probability(target);
+            match roll.result {
+                RollResult::Critical => luck.criticals += 1,
+                RollResult::Fumble => luck.fumbles += 1,
+                _ => {}
+            }
+        }
+        luck
+    }
+
+    // 期待より何回多く成功したか
+    pub fn index(&self) -> f64 {
+        self.successes as f64 - self.expected_successes
+    }
+}
+
+impl Display for Luck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::new();
+        s.push_str(&format!(
+            "成功数：{}（期待値：{:.1}）\n",
+            self.successes, self.expected_successes
+        ));
+        s.push_str(&format!(
+            "クリティカル数：{}（期待値：{:.2}）\n",
+            self.criticals, self.expected_criticals
+        ));
+        s.push_str(&format!(
+            "ファンブル数：{}（期待値：{:.2}）\n",
+            self.fumbles, self.expected_fumbles
+        ));
+        s.push_str(&format!("運勢指数：{:+.1}\n", self.index()));
+        write!(f, "{s}")
+    }
+}
+
+// 運が良かった順に並べる
+pub fn rank_by_luck(lucks: &HashMap<String, Luck>) -> Vec<(&str, &Luck)> {
+    let mut ranking: Vec<_> = lucks
+        .iter()
+        .filter(|(_, luck)| luck.rolls > 0)
+        .map(|(name, luck)| (name.as_str(), luck))
+        .collect();
+    ranking.sort_by(|(a_name, a), (b_name, b)| {
+        b.index()
+            .total_cmp(&a.index())
+            .then_with(|| a_name.cmp(b_name))
+    });
+    ranking
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(name: &str, texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_luck() {
+        let logs = vec![log(
+            "PC1",
+            &[
+                "CCB<=5 【目星】 (1D100<=5) ＞ 3 ＞ 決定的成功/スペシャル",
+                "CCB<=80 【回避】 (1D100<=80) ＞ 98 ＞ 致命的失敗",
+                "1d10 (1D10) ＞ 8 ＞ 成功",
+            ],
+        )];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let luck = Luck::new(&rolls, GameSystem::Coc6);
+
+        assert_eq!(luck.rolls, 2);
+        assert_eq!(luck.successes, 1);
+        assert_eq!(luck.criticals, 1);
+        assert_eq!(luck.fumbles, 1);
+        assert!((luck.expected_successes - 0.85).abs() < 1e-9);
+        assert!((luck.expected_criticals - 0.10).abs() < 1e-9);
+        assert!((luck.expected_fumbles - 0.10).abs() < 1e-9);
+        assert!((luck.index() - 0.15).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_combination_luck() {
+        let logs = vec![log(
+            "PC1",
+            &["CBRB(50,60) (1d100<=50,60) ＞ 3[決定的成功,決定的成功] ＞ 成功"],
+        )];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let luck = Luck::new(&rolls, GameSystem::Coc6);
+
+        assert_eq!(luck.rolls, 2);
+        assert_eq!(luck.successes, 2);
+        assert_eq!(luck.criticals, 1);
+        assert!((luck.expected_successes - 1.1).abs() < 1e-9);
+        assert!((luck.expected_criticals - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rank_by_luck() {
+        let logs = vec![
+            log("不運", &["CCB<=90 (1D100<=90) ＞ 95 ＞ 失敗"]),
+            log("幸運", &["CCB<=10 (1D100<=10) ＞ 7 ＞ 成功"]),
+            log("見学", &["こんにちは"]),
+        ];
+        let rolls = parse_rolls(&logs);
+        let mut lucks = HashMap::new();
+        for log in &logs {
+            let rolls: Vec<&Roll> = rolls.iter().filter(|r| r.name() == log.name).collect();
+            lucks.insert(log.name.clone(), Luck::new(&rolls, GameSystem::Coc6));
+        }
+
+        let ranking = rank_by_luck(&lucks);
+        let names: Vec<&str> = ranking.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["幸運", "不運"]);
+    }
+}
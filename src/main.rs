@@ -1,40 +1,270 @@
 use ccfolia_log_parser::error::MyError;
-use ccfolia_log_parser::log_summary::{LogSummary, UserChoice}; // Added UserChoice and LogSummary
-use ccfolia_log_parser::{get_log_summary, get_logs, get_pc_summary};
-use std::{env, io, io::Write}; // Added io::Write
+use ccfolia_log_parser::log_summary::UserChoice;
+use ccfolia_log_parser::luck::rank_by_luck;
+use ccfolia_log_parser::options::Options;
+use ccfolia_log_parser::system::GameSystem;
+use ccfolia_log_parser::{
+    get_awards, get_config, get_inconsistencies, get_kp_dx3, get_kp_emoklore, get_kp_scene,
+    get_kp_summary, get_kp_sw25, get_log_summary, get_logs_from_files, get_palettes, get_pc_dice,
+    get_pc_dx3, get_pc_emoklore, get_pc_fairness, get_pc_growth_checks, get_pc_house_rule,
+    get_pc_luck, get_pc_random_results, get_pc_scene, get_pc_streaks, get_pc_summary_with_roles,
+    get_pc_sw25, get_player_summary, get_random_results, get_reroll_suspects,
+    get_result_mismatches, get_roles, get_rolls, get_secret_report, get_sheet_mismatches,
+    get_sheets, get_skill_matrix, get_system, get_table_fairness, hide_secrets, merge_names,
+};
+use std::collections::HashMap;
+use std::{env, fs, io, io::Write}; // Added io::Write
+
+fn exit_with_error(e: MyError) {
+    match e {
+        MyError::Io(e) => eprintln!("ファイルが開けませんでした：{}", e),
+        MyError::Parse(e) => eprintln!("Parse error:{}", e),
+    }
+    println!("Enterキーで終了します");
+    let mut a = "".to_string();
+    io::stdin().read_line(&mut a).expect("Failed to read line");
+}
 
 fn main() {
-    let original_logs = match get_logs(env::args()) {
+    let options = match Options::parse(env::args()) {
+        Ok(options) => options,
+        Err(e) => return exit_with_error(MyError::from(e)),
+    };
+
+    let config = match get_config(&options) {
+        Ok(config) => config,
+        Err(e) => return exit_with_error(e),
+    };
+
+    let mut original_logs = match get_logs_from_files(&options.log_paths) {
         Ok(logs) => logs,
-        Err(e) => match e {
-            MyError::Io(e) => {
-                eprintln!("ファイルが開けませんでした：{}", e);
-                println!("Enterキーで終了します");
-                let mut a = "".to_string();
-                io::stdin().read_line(&mut a).expect("Failed to read line");
-                return;
-            }
-            MyError::Parse(e) => {
-                eprintln!("Parse error:{}", e);
-                println!("Enterキーで終了します");
-                let mut a = "".to_string();
-                io::stdin().read_line(&mut a).expect("Failed to read line");
-                return;
-            }
-        },
+        Err(e) => return exit_with_error(e),
+    };
+    merge_names(&mut original_logs, &config);
+
+    // シークレットダイスの結果はKPだけが見るものなので、画面には出さずファイルにだけ書き出す
+    if let Some(path) = &options.secret_report_path {
+        match fs::write(path, get_secret_report(&original_logs).to_string()) {
+            Ok(()) => println!("シークレットダイスの結果を{}に書き出しました\n", path),
+            Err(e) => eprintln!("シークレットダイスの結果を書き出せませんでした：{}\n", e),
+        }
+    }
+    if options.hide_secret {
+        original_logs = hide_secrets(&original_logs);
+    }
+
+    let sheets = match get_sheets(&options.sheet_paths, &config) {
+        Ok(sheets) => sheets,
+        Err(e) => return exit_with_error(e),
+    };
+    let palettes = match get_palettes(&options.palette_paths, &sheets, &config) {
+        Ok(palettes) => palettes,
+        Err(e) => return exit_with_error(e),
     };
 
     let _logs = get_log_summary(&original_logs);
 
-    let log_summary_by_name = get_pc_summary(&original_logs);
+    let (system, detection) = get_system(&options, &config, &original_logs);
+    match &detection {
+        Some(detection) => println!("ゲームシステム：{}\n", detection),
+        None => println!("ゲームシステム：{}\n", system.to_display_string()),
+    }
+
+    let roles = get_roles(&original_logs, &config);
+    let log_summary_by_name = get_pc_summary_with_roles(&original_logs, &roles, &palettes);
+
+    let rolls = get_rolls(&original_logs, &palettes);
+    let luck_by_name = get_pc_luck(&rolls, &roles, system);
+    let streaks_by_name = get_pc_streaks(&rolls, &roles);
+    let house_rule_by_name = match config.critical_rule {
+        Some(rule) => get_pc_house_rule(&rolls, &roles, rule),
+        None => HashMap::new(),
+    };
 
     // Initial Display Loop (Counts Only)
     println!("--- 各プレイヤーの集計結果 ---");
-    for (name, log_summary) in &log_summary_by_name {
-        println!("{}：\n{}", name, log_summary.to_string()); // Uses Display trait
+    match system {
+        // エモクロアは成功・失敗ではなく成功数で集計する
+        GameSystem::Emoklore => {
+            for (name, summary) in &get_pc_emoklore(&original_logs, &roles) {
+                println!("{}：\n{}", name, summary);
+            }
+        }
+        GameSystem::Sw25 => {
+            for (name, summary) in &get_pc_sw25(&original_logs, &roles) {
+                println!("{}：\n{}", name, summary);
+            }
+        }
+        GameSystem::Dx3 => {
+            for (name, summary) in &get_pc_dx3(&original_logs, &roles) {
+                println!("{}：\n{}", name, summary);
+            }
+        }
+        GameSystem::Shinobigami | GameSystem::Insane => {
+            for (name, summary) in &get_pc_scene(&original_logs, &roles) {
+                println!("{}：\n{}", name, summary);
+            }
+        }
+        GameSystem::Coc6 => {
+            for (name, log_summary) in &log_summary_by_name {
+                let mut s = format!("{}：\n{}", name, log_summary); // Uses Display trait
+                if let Some(luck) = luck_by_name.get(name) {
+                    s.push_str(&luck.to_string());
+                }
+                if let Some(streaks) = streaks_by_name.get(name) {
+                    s.push_str(&streaks.to_string());
+                }
+                if let Some(house_rule) = house_rule_by_name.get(name) {
+                    s.push_str(&house_rule.to_string());
+                }
+                println!("{}", s);
+            }
+        }
     }
     println!("---------------------------\n");
 
+    let dice_by_name = get_pc_dice(&original_logs, &roles);
+    if !dice_by_name.is_empty() {
+        println!("--- ダメージ・ダイスの合計 ---");
+        for (name, summary) in &dice_by_name {
+            println!("{}：\n{}", name, summary);
+        }
+        println!("---------------------------\n");
+    }
+
+    let random_results = get_random_results(&original_logs);
+    if !random_results.is_empty() {
+        println!("--- choice・ランダム表の結果 ---");
+        for result in &random_results {
+            println!("{}", result);
+        }
+        for (name, results) in &get_pc_random_results(&random_results, &roles) {
+            println!("\n{}：", name);
+            for result in results {
+                println!("  {} ＞ {}", result.source, result.result);
+            }
+        }
+        println!("---------------------------\n");
+    }
+
+    // 運勢指数はCoC6の技能値から期待値を出すのでCoC6のときだけ
+    if system == GameSystem::Coc6 {
+        println!("--- 運勢ランキング ---");
+        for (rank, (name, luck)) in rank_by_luck(&luck_by_name).iter().enumerate() {
+            println!("{}位：{}（{:+.1}）", rank + 1, name, luck.index());
+        }
+        println!("---------------------------\n");
+    }
+
+    let non_pc_names = roles.non_pc_names();
+    if !non_pc_names.is_empty() {
+        println!("--- KP/NPCの集計結果 ---");
+        let names = non_pc_names
+            .iter()
+            .map(|(name, role)| format!("{}（{}）", name, role.to_display_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let summary = match system {
+            GameSystem::Emoklore => get_kp_emoklore(&original_logs, &roles).to_string(),
+            GameSystem::Sw25 => get_kp_sw25(&original_logs, &roles).to_string(),
+            GameSystem::Dx3 => get_kp_dx3(&original_logs, &roles).to_string(),
+            GameSystem::Shinobigami | GameSystem::Insane => {
+                get_kp_scene(&original_logs, &roles).to_string()
+            }
+            GameSystem::Coc6 => get_kp_summary(&original_logs, &roles).to_string(),
+        };
+        println!("{}\n{}", names, summary);
+        println!("---------------------------\n");
+    }
+
+    // プレイヤー別の集計はCoC6の成功・失敗で数えるのでCoC6のときだけ
+    let player_summary = get_player_summary(&original_logs, &config);
+    if system == GameSystem::Coc6 && !player_summary.is_empty() {
+        println!("--- プレイヤー別の集計結果 ---");
+        for (player, summary) in &player_summary {
+            println!("{}：\n{}", player, summary);
+        }
+        println!("---------------------------\n");
+    }
+
+    // 1D100の出目を見るのでCoC6のときだけ
+    if system == GameSystem::Coc6 {
+        println!("--- 出目の偏り（{}） ---", system.to_display_string());
+        for (name, fairness) in &get_pc_fairness(&rolls, &roles, system, &config) {
+            println!("{}：\n{}", name, fairness);
+        }
+        println!("卓全体：\n{}", get_table_fairness(&rolls, system, &config));
+        println!("---------------------------\n");
+    }
+
+    // 成長判定はCoC6のルール
+    if system == GameSystem::Coc6 {
+        println!("--- 成長判定 ---");
+        for (name, growth) in &get_pc_growth_checks(&rolls, &roles) {
+            print!("{}：{}", name, growth);
+            if options.growth_palette {
+                print!("{}", growth.to_palette());
+            }
+        }
+        println!("---------------------------\n");
+    }
+
+    let inconsistencies = get_inconsistencies(&rolls, &roles);
+    if !inconsistencies.is_empty() {
+        println!("--- 目標値の食い違い ---");
+        for inconsistency in &inconsistencies {
+            println!("{}", inconsistency);
+        }
+        println!("---------------------------\n");
+    }
+
+    let result_mismatches = get_result_mismatches(&rolls, system, &config);
+    if !result_mismatches.is_empty() {
+        println!("--- 結果の食い違い ---");
+        for mismatch in &result_mismatches {
+            println!("{}", mismatch);
+        }
+        println!("---------------------------\n");
+    }
+
+    let reroll_suspects = get_reroll_suspects(&rolls, &roles, config.reroll_window);
+    if !reroll_suspects.is_empty() {
+        println!("--- 振り直しの疑い ---");
+        for suspect in &reroll_suspects {
+            println!("{}", suspect);
+        }
+        println!("---------------------------\n");
+    }
+
+    let sheet_mismatches = get_sheet_mismatches(&rolls, &roles, &sheets);
+    if !sheet_mismatches.is_empty() {
+        println!("--- キャラクターシートとの食い違い ---");
+        for mismatch in &sheet_mismatches {
+            println!("{}", mismatch);
+        }
+        println!("---------------------------\n");
+    }
+
+    let skill_matrix = get_skill_matrix(&rolls, &roles, &sheets);
+    println!("--- 技能ごとの成功数/試行数（C：クリティカル、F：ファンブル、括弧内は技能値） ---");
+    print!("{}", skill_matrix);
+    println!("---------------------------\n");
+    if let Some(path) = &options.matrix_csv_path {
+        match fs::write(path, skill_matrix.to_csv()) {
+            Ok(()) => println!("技能表を{}に書き出しました\n", path),
+            Err(e) => eprintln!("技能表を書き出せませんでした：{}\n", e),
+        }
+    }
+
+    let awards = get_awards(&original_logs, &rolls, &roles, &config);
+    if options.markdown {
+        println!("{}", awards.to_markdown());
+    } else {
+        println!("--- 今回のハイライト ---");
+        print!("{}", awards);
+        println!("---------------------------\n");
+    }
+
     // User Input Section
     println!("どの結果の技能一覧を詳しく見ますか？");
     println!("1: 成功");
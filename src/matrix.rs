@@ -0,0 +1,330 @@
+use crate::roll::{Roll, RollResult};
+use crate::sheet::CharacterSheet;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+
+// PCと技能の組ごとのロール結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cell {
+    pub attempts: usize,
+    // クリティカルを含む
+    pub successes: usize,
+    pub criticals: usize,
+    pub fumbles: usize,
+}
+
+impl Cell {
+    // 「2/3 C1 F1」、技能値が分かれば「2/3 C1 F1 (75)」
+    fn to_short_string(self, rating: Option<u32>) -> String {
+        if self.attempts == 0 {
+            return "-".to_string();
+        }
+        let mut s = format!("{}/{}", self.successes, self.attempts);
+        if self.criticals > 0 {
+            s.push_str(&format!(" C{}", self.criticals));
+        }
+        if self.fumbles > 0 {
+            s.push_str(&format!(" F{}", self.fumbles));
+        }
+        if let Some(rating) = rating {
+            s.push_str(&format!(" ({})", rating));
+        }
+        s
+    }
+}
+
+// PC×技能の成功率表
+pub struct SkillMatrix {
+    pub names: Vec<String>,
+    pub skills: Vec<String>,
+    cells: HashMap<(String, String), Cell>,
+    // キャラクターシートの技能値
+    ratings: HashMap<(String, String), u32>,
+}
+
+impl SkillMatrix {
+    pub fn new(rolls_by_name: &HashMap<String, Vec<&Roll>>) -> SkillMatrix {
+        let mut cells: HashMap<(String, String), Cell> = HashMap::new();
+        let mut skills = BTreeSet::new();
+        for (name, rolls) in rolls_by_name {
+            for roll in rolls {
+                let skill = roll.skill_name();
+                skills.insert(skill.clone());
+                let cell = cells.entry((name.clone(), skill)).or_default();
+                cell.attempts += 1;
+                if roll.result.is_success() {
+                    cell.successes += 1;
+                }
+                match roll.result {
+                    RollResult::Critical => cell.criticals += 1,
+                    RollResult::Fumble => cell.fumbles += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut names: Vec<String> = rolls_by_name.keys().cloned().collect();
+        names.sort();
+        SkillMatrix {
+            names,
+            skills: skills.into_iter().collect(),
+            cells,
+            ratings: HashMap::new(),
+        }
+    }
+
+    pub fn set_ratings(&mut self, sheets: &HashMap<String, CharacterSheet>) {
+        for name in &self.names {
+            let Some(sheet) = sheets.get(name) else {
+                continue;
+            };
+            for skill in &self.skills {
+                if let Some(value) = sheet.skill_value(skill) {
+                    self.ratings.insert((name.clone(), skill.clone()), value);
+                }
+            }
+        }
+    }
+
+    pub fn rating(&self, name: &str, skill: &str) -> Option<u32> {
+        self.ratings
+            .get(&(name.to_string(), skill.to_string()))
+            .copied()
+    }
+
+    pub fn cell(&self, name: &str, skill: &str) -> Cell {
+        self.cells
+            .get(&(name.to_string(), skill.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // 縦に技能、横にPCを並べた表
+    pub fn to_table(&self) -> String {
+        let mut rows = vec![];
+        let mut header = vec!["技能".to_string()];
+        header.extend(self.names.iter().cloned());
+        rows.push(header);
+        for skill in &self.skills {
+            let mut row = vec![skill.clone()];
+            for name in &self.names {
+                row.push(
+                    self.cell(name, skill)
+                        .to_short_string(self.rating(name, skill)),
+                );
+            }
+            rows.push(row);
+        }
+
+        let columns = rows[0].len();
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                rows.iter()
+                    .map(|row| display_width(&row[i]))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut s = String::new();
+        for row in &rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(text, width)| format!("{}{}", text, " ".repeat(width - display_width(text))))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+        s
+    }
+
+    // 表計算ソフト向けに1行1セルで書き出す
+    pub fn to_csv(&self) -> String {
+        let mut s = String::from("PC,技能,試行,成功,クリティカル,ファンブル,技能値\n");
+        for name in &self.names {
+            for skill in &self.skills {
+                let cell = self.cell(name, skill);
+                if cell.attempts == 0 {
+                    continue;
+                }
+                s.push_str(&format!(
+                    "{},{},{},{},{},{},{}\n",
+                    csv_field(name),
+                    csv_field(skill),
+                    cell.attempts,
+                    cell.successes,
+                    cell.criticals,
+                    cell.fumbles,
+                    self.rating(name, skill)
+                        .map_or(String::new(), |rating| rating.to_string())
+                ));
+            }
+        }
+        s
+    }
+}
+
+impl Display for SkillMatrix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// 端末での表示幅（全角文字は2）
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    fn matrix(logs: &[Log]) -> SkillMatrix {
+        let rolls = parse_rolls(logs);
+        let mut rolls_by_name: HashMap<String, Vec<&Roll>> = HashMap::new();
+        for roll in &rolls {
+            rolls_by_name
+                .entry(roll.name().to_string())
+                .or_default()
+                .push(roll);
+        }
+        SkillMatrix::new(&rolls_by_name)
+    }
+
+    #[test]
+    fn test_matrix_cells() {
+        let logs = vec![
+            log(
+                "イオリ",
+                "CCB<=25 【目星】 (1D100<=25) ＞ 3 ＞ 決定的成功/スペシャル",
+            ),
+            log("イオリ", "CCB<=35 【目星+10】 (1D100<=35) ＞ 50 ＞ 失敗"),
+            log("イオリ", "CCB<=50 【回避】 (1D100<=50) ＞ 99 ＞ 致命的失敗"),
+            log("ソウタ", "CCB<=70 【図書館】 (1D100<=70) ＞ 20 ＞ 成功"),
+        ];
+        let matrix = matrix(&logs);
+
+        assert_eq!(matrix.names, vec!["イオリ", "ソウタ"]);
+        assert_eq!(matrix.skills, vec!["回避", "図書館", "目星"]);
+        assert_eq!(
+            matrix.cell("イオリ", "目星"),
+            Cell {
+                attempts: 2,
+                successes: 1,
+                criticals: 1,
+                fumbles: 0
+            }
+        );
+        assert_eq!(matrix.cell("ソウタ", "目星"), Cell::default());
+    }
+
+    #[test]
+    fn test_matrix_specialized_skills() {
+        let logs = vec![
+            log("イオリ", "CCB<=60 【芸術(絵画)】 (1D100<=60) ＞ 20 ＞ 成功"),
+            log(
+                "イオリ",
+                "CCB<=40 【芸術（歌唱）】 (1D100<=40) ＞ 70 ＞ 失敗",
+            ),
+            log(
+                "イオリ",
+                "CCB<=40 【芸術(歌唱)+10】 (1D100<=40) ＞ 30 ＞ 成功",
+            ),
+        ];
+        let matrix = matrix(&logs);
+
+        assert_eq!(matrix.skills, vec!["芸術(歌唱)", "芸術(絵画)"]);
+        assert_eq!(matrix.cell("イオリ", "芸術(絵画)").attempts, 1);
+        assert_eq!(matrix.cell("イオリ", "芸術(歌唱)").attempts, 2);
+        assert_eq!(matrix.cell("イオリ", "芸術(歌唱)").successes, 1);
+    }
+
+    #[test]
+    fn test_matrix_table_and_csv() {
+        let logs = vec![
+            log(
+                "イオリ",
+                "CCB<=25 【目星】 (1D100<=25) ＞ 3 ＞ 決定的成功/スペシャル",
+            ),
+            log("Bob", "CCB<=50 【回避】 (1D100<=50) ＞ 99 ＞ 致命的失敗"),
+        ];
+        let matrix = matrix(&logs);
+
+        assert_eq!(
+            matrix.to_table(),
+            "技能 | Bob    | イオリ\n\
+             回避 | 0/1 F1 | -\n\
+             目星 | -      | 1/1 C1\n"
+        );
+        assert_eq!(
+            matrix.to_csv(),
+            "PC,技能,試行,成功,クリティカル,ファンブル,技能値\n\
+             Bob,回避,1,0,0,1,\n\
+             イオリ,目星,1,1,1,0,\n"
+        );
+    }
+
+    #[test]
+    fn test_matrix_ratings() {
+        let logs = vec![
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"),
+            log("イオリ", "CCB<=50 【回避】 (1D100<=50) ＞ 80 ＞ 失敗"),
+        ];
+        let mut matrix = matrix(&logs);
+        let sheet =
+            CharacterSheet::parse(r#"{"name":"イオリ","commands":"CCB<=25 【目星】"}"#).unwrap();
+        matrix.set_ratings(&HashMap::from([("イオリ".to_string(), sheet)]));
+
+        assert_eq!(matrix.rating("イオリ", "目星"), Some(25));
+        assert_eq!(matrix.rating("イオリ", "回避"), None);
+        assert_eq!(
+            matrix.to_table(),
+            "技能 | イオリ\n\
+             回避 | 0/1\n\
+             目星 | 1/1 (25)\n"
+        );
+        assert!(matrix.to_csv().ends_with("イオリ,目星,1,1,0,0,25\n"));
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("目星"), "目星");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("\"引用\""), "\"\"\"引用\"\"\"");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::ParseError;
+use crate::system::GameSystem;
+
+const DEFAULT_LOG_PATH: &str = "data/log5.html";
+pub const DEFAULT_CONFIG_PATH: &str = "config.ini";
+
+// コマンドライン引数
+// ccfolia-log-parser [ログのパス...] [--config 設定ファイルのパス] [--markdown]
+//                    [--matrix-csv 技能表の出力先] [--growth-palette]
+//                    [--sheet キャラクターのJSON...] [--palette 名前=チャットパレット...]
+//                    [--hide-secret] [--secret-report KP用の出力先] [--system ゲームシステム]
+pub struct Options {
+    pub log_paths: Vec<String>,
+    pub config_path: Option<String>,
+    // ハイライトをMarkdownで出力する
+    pub markdown: bool,
+    pub matrix_csv_path: Option<String>,
+    // 成長判定のチャットパレットを出力する
+    pub growth_palette: bool,
+    // ccfoliaのキャラクターJSON（--sheetを繰り返して複数指定できる）
+    pub sheet_paths: Vec<String>,
+    // キャラクター名とチャットパレットのテキストファイルの組
+    pub palette_paths: Vec<(String, String)>,
+    // シークレットダイスを集計から除く
+    pub hide_secret: bool,
+    // シークレットダイスの結果をKP用に書き出す
+    pub secret_report_path: Option<String>,
+    // 設定ファイルや自動判定より優先する
+    pub system: Option<GameSystem>,
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, ParseError> {
+        // 先頭は実行ファイル名
+        args.next();
+
+        let mut log_paths = Vec::new();
+        let mut config_path = None;
+        let mut markdown = false;
+        let mut matrix_csv_path = None;
+        let mut growth_palette = false;
+        let mut sheet_paths = Vec::new();
+        let mut palette_paths = Vec::new();
+        let mut hide_secret = false;
+        let mut secret_report_path = None;
+        let mut system = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => config_path = Some(Options::value_of(&arg, args.next())?),
+                "--markdown" => markdown = true,
+                "--growth-palette" => growth_palette = true,
+                "--matrix-csv" => matrix_csv_path = Some(Options::value_of(&arg, args.next())?),
+                "--hide-secret" => hide_secret = true,
+                "--system" => {
+                    system = Some(GameSystem::from_name(&Options::value_of(
+                        &arg,
+                        args.next(),
+                    )?)?)
+                }
+                "--secret-report" => {
+                    secret_report_path = Some(Options::value_of(&arg, args.next())?)
+                }
+                "--sheet" => sheet_paths.push(Options::value_of(&arg, args.next())?),
+                "--palette" => {
+                    let value = Options::value_of(&arg, args.next())?;
+                    match value.split_once('=') {
+                        Some((name, path)) if !name.trim().is_empty() && !path.is_empty() => {
+                            palette_paths.push((name.trim().to_string(), path.to_string()))
+                        }
+                        _ => {
+                            return Err(ParseError {
+                                string: format!(
+                                    "--paletteは「名前=パス」の形で指定してください：{}",
+                                    value
+                                ),
+                            })
+                        }
+                    }
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(ParseError {
+                        string: format!("不明なオプションです：{}", arg),
+                    })
+                }
+                _ => log_paths.push(arg),
+            }
+        }
+
+        if log_paths.is_empty() {
+            log_paths.push(DEFAULT_LOG_PATH.to_string());
+        }
+
+        Ok(Options {
+            log_paths,
+            config_path,
+            markdown,
+            matrix_csv_path,
+            growth_palette,
+            sheet_paths,
+            palette_paths,
+            hide_secret,
+            secret_report_path,
+            system,
+        })
+    }
+
+    fn value_of(option: &str, value: Option<String>) -> Result<String, ParseError> {
+        value.ok_or_else(|| ParseError {
+            string: format!("{}の値が指定されていません", option),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, ParseError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let options = parse(&["exe"]).unwrap();
+        assert_eq!(options.log_paths, vec![DEFAULT_LOG_PATH]);
+        assert_eq!(options.config_path, None);
+        assert!(!options.markdown);
+        assert_eq!(options.matrix_csv_path, None);
+        assert!(!options.growth_palette);
+        assert!(options.sheet_paths.is_empty());
+        assert!(!options.hide_secret);
+        assert_eq!(options.secret_report_path, None);
+        assert_eq!(options.system, None);
+    }
+
+    #[test]
+    fn test_parse_path_and_config() {
+        let options = parse(&[
+            "exe",
+            "--config",
+            "my.ini",
+            "log1.html",
+            "--markdown",
+            "log2.html",
+        ])
+        .unwrap();
+        assert_eq!(options.log_paths, vec!["log1.html", "log2.html"]);
+        assert_eq!(options.config_path, Some("my.ini".to_string()));
+        assert!(options.markdown);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["exe", "--config"]).is_err());
+        assert!(parse(&["exe", "--matrix-csv"]).is_err());
+        assert!(parse(&["exe", "--unknown"]).is_err());
+        assert!(parse(&["exe", "--sheet"]).is_err());
+        assert!(parse(&["exe", "--secret-report"]).is_err());
+        assert!(parse(&["exe", "--system", "dnd"]).is_err());
+        assert!(parse(&["exe", "--palette", "palette.txt"]).is_err());
+        assert!(parse(&["exe", "--palette", "=palette.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_sheets() {
+        let options = parse(&[
+            "exe",
+            "--sheet",
+            "iori.json",
+            "log.html",
+            "--sheet",
+            "sota.json",
+        ])
+        .unwrap();
+        assert_eq!(options.log_paths, vec!["log.html"]);
+        assert_eq!(options.sheet_paths, vec!["iori.json", "sota.json"]);
+    }
+
+    #[test]
+    fn test_parse_secret() {
+        let options = parse(&["exe", "--hide-secret", "--secret-report", "kp.txt"]).unwrap();
+        assert!(options.hide_secret);
+        assert_eq!(options.secret_report_path, Some("kp.txt".to_string()));
+    }
+
+    #[test]
+    fn test_parse_system() {
+        let options = parse(&["exe", "--system", "dx3"]).unwrap();
+        assert_eq!(options.system, Some(GameSystem::Dx3));
+    }
+
+    #[test]
+    fn test_parse_palettes() {
+        let options = parse(&["exe", "--palette", "イオリ=iori.txt"]).unwrap();
+        assert_eq!(
+            options.palette_paths,
+            vec![("イオリ".to_string(), "iori.txt".to_string())]
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use crate::error::MyError;
+use crate::roll::{normalize_skill, strip_secret_prefix, Roll, RollKind};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::Read;
+
+// チャットパレットの1行
+// 例：CCB<={目星} 【目星】、CCB<=75 目星、CCB<={目星}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub command: String,
+    // 【】の中、なければコマンドの後ろのコメント、それもなければ{目星}のような参照の名前
+    pub skill: Option<String>,
+    // {目星}のような参照を展開した目標値
+    pub target: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChatPalette {
+    pub entries: Vec<PaletteEntry>,
+}
+
+impl PaletteEntry {
+    // 「CCB<=75 【目星】」の「CCB<=75」
+    pub fn dice(&self) -> &str {
+        self.command.split_whitespace().next().unwrap_or("")
+    }
+}
+
+impl ChatPalette {
+    pub fn load(filename: &str, variables: &HashMap<String, i64>) -> Result<ChatPalette, MyError> {
+        let mut file = File::open(filename)?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        Ok(ChatPalette::parse(&text, variables))
+    }
+
+    // variablesは{名前}の参照先（キャラクターのステータス・パラメータ）
+    pub fn parse(text: &str, variables: &HashMap<String, i64>) -> ChatPalette {
+        let entries = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| PaletteEntry {
+                command: line.to_string(),
+                skill: parse_label(line)
+                    .or_else(|| parse_comment(line))
+                    .or_else(|| parse_reference(line)),
+                target: parse_palette_target(line, variables),
+            })
+            .collect();
+        ChatPalette { entries }
+    }
+
+    // 技能名（正規化済み）と技能値の組
+    pub fn skills(&self) -> Vec<(String, u32)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| Some((normalize_skill(entry.skill.as_ref()?), entry.target?)))
+            .collect()
+    }
+
+    pub fn extend(&mut self, other: ChatPalette) {
+        self.entries.extend(other.entries);
+    }
+
+    // ログに残ったコマンドから技能名を探す
+    // commandは「CCB<=75 (1D100<=75)」のような「＞」より前の部分
+    pub fn skill_for(&self, command: &str, target: Option<u32>) -> Option<String> {
+        let dice = command.split_whitespace().next()?;
+        let exact = self
+            .entries
+            .iter()
+            .filter(|entry| entry.dice().eq_ignore_ascii_case(dice))
+            .find_map(|entry| entry.skill.clone());
+        if exact.is_some() {
+            return exact;
+        }
+
+        // 同じ目標値の技能が一つしかなければそれとみなす
+        // 対抗ロールや組み合わせロールを技能と取り違えないよう、1D100の技能判定どうしに限る
+        let target = target?;
+        if !is_skill_check(dice) {
+            return None;
+        }
+        let skills: BTreeSet<&String> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.target == Some(target) && is_skill_check(entry.dice()))
+            .filter_map(|entry| entry.skill.as_ref())
+            .collect();
+        if skills.len() == 1 {
+            skills.into_iter().next().cloned()
+        } else {
+            None
+        }
+    }
+}
+
+// CCB<=75・CC<=75・1D100<=75のような1D100の技能判定か
+fn is_skill_check(dice: &str) -> bool {
+    let dice = dice.to_uppercase();
+    let dice = strip_secret_prefix(&dice).unwrap_or(&dice);
+    dice.starts_with("CC") || dice.starts_with("1D100<=")
+}
+
+// 【】のないロールにパレットから技能名を補う
+// 対抗ロールは技能ではないので補わない
+pub fn apply_palettes(rolls: &mut [Roll], palettes: &HashMap<String, ChatPalette>) {
+    for roll in rolls {
+        if roll.skill.is_some() || roll.kind == RollKind::Resistance {
+            continue;
+        }
+        if let Some(palette) = palettes.get(roll.name()) {
+            roll.skill = palette.skill_for(&roll.command, roll.target);
+        }
+    }
+}
+
+// 技能名の入っていないコマンドに「【目星】」を差し込む
+// 「CCB<=75 (1D100<=75)」->「CCB<=75 【目星】 (1D100<=75)」
+pub fn insert_label(command: &str, skill: &str) -> String {
+    match command.find(" (") {
+        Some(index) => format!("{} 【{}】{}", &command[..index], skill, &command[index..]),
+        None => format!("{} 【{}】", command, skill),
+    }
+}
+
+fn parse_label(line: &str) -> Option<String> {
+    let start = line.find('【')? + '【'.len_utf8();
+    let end = start + line[start..].find('】')?;
+    let label = line[start..end].trim();
+    if label.is_empty() {
+        None
+    } else {
+        Some(label.to_string())
+    }
+}
+
+// 「CCB<=75 目星」の「目星」
+fn parse_comment(line: &str) -> Option<String> {
+    let (_, comment) = line.split_once(char::is_whitespace)?;
+    let comment = comment.trim();
+    if comment.is_empty() {
+        None
+    } else {
+        Some(comment.to_string())
+    }
+}
+
+// 目標値が「{目星}」だけのときはその名前を使う
+fn parse_reference(line: &str) -> Option<String> {
+    let start = line.find("<=")? + "<=".len();
+    let expression = line[start..].split_whitespace().next()?;
+    let name = expression.strip_prefix('{')?.strip_suffix('}')?.trim();
+    if name.is_empty() || name.contains(['{', '}']) {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+// 「<=」の後ろの式を計算する
+fn parse_palette_target(line: &str, variables: &HashMap<String, i64>) -> Option<u32> {
+    let start = line.find("<=")? + "<=".len();
+    let expression: String = line[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '【')
+        .collect();
+    let value = evaluate(&expression, variables)?;
+    u32::try_from(value).ok()
+}
+
+// 整数・{参照}・+ - * / と括弧だけの簡単な式
+fn evaluate(expression: &str, variables: &HashMap<String, i64>) -> Option<i64> {
+    let tokens = tokenize(expression, variables)?;
+    let mut position = 0;
+    let value = parse_sum(&tokens, &mut position)?;
+    if position == tokens.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(i64),
+    Operator(char),
+}
+
+fn tokenize(expression: &str, variables: &HashMap<String, i64>) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => {
+                let mut number = c.to_digit(10)? as i64;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    number = number * 10 + digit as i64;
+                    chars.next();
+                }
+                tokens.push(Token::Number(number));
+            }
+            '{' => {
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                tokens.push(Token::Number(*variables.get(name.trim())?));
+            }
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(Token::Operator(c)),
+            '×' => tokens.push(Token::Operator('*')),
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+fn parse_sum(tokens: &[Token], position: &mut usize) -> Option<i64> {
+    let mut value = parse_product(tokens, position)?;
+    while let Some(Token::Operator(op @ ('+' | '-'))) = tokens.get(*position) {
+        *position += 1;
+        let rhs = parse_product(tokens, position)?;
+        value = if *op == '+' { value + rhs } else { value - rhs };
+    }
+    Some(value)
+}
+
+fn parse_product(tokens: &[Token], position: &mut usize) -> Option<i64> {
+    let mut value = parse_term(tokens, position)?;
+    while let Some(Token::Operator(op @ ('*' | '/'))) = tokens.get(*position) {
+        *position += 1;
+        let rhs = parse_term(tokens, position)?;
+        value = if *op == '*' {
+            value * rhs
+        } else {
+            value.checked_div(rhs)?
+        };
+    }
+    Some(value)
+}
+
+fn parse_term(tokens: &[Token], position: &mut usize) -> Option<i64> {
+    match tokens.get(*position)? {
+        Token::Number(number) => {
+            *position += 1;
+            Some(*number)
+        }
+        Token::Operator('(') => {
+            *position += 1;
+            let value = parse_sum(tokens, position)?;
+            if tokens.get(*position) != Some(&Token::Operator(')')) {
+                return None;
+            }
+            *position += 1;
+            Some(value)
+        }
+        Token::Operator('-') => {
+            *position += 1;
+            Some(-parse_term(tokens, position)?)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> HashMap<String, i64> {
+        HashMap::from([
+            ("目星".to_string(), 75),
+            ("POW".to_string(), 12),
+            ("SAN".to_string(), 60),
+        ])
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let variables = variables();
+        assert_eq!(evaluate("75", &variables), Some(75));
+        assert_eq!(evaluate("{目星}", &variables), Some(75));
+        assert_eq!(evaluate("({POW}*5)", &variables), Some(60));
+        assert_eq!(evaluate("{POW}×5+10", &variables), Some(70));
+        assert_eq!(evaluate("{目星}/2", &variables), Some(37));
+        assert_eq!(evaluate("{不明}", &variables), None);
+        assert_eq!(evaluate("1d100", &variables), None);
+        assert_eq!(evaluate("(1+2", &variables), None);
+    }
+
+    #[test]
+    fn test_parse_palette() {
+        let text = "CCB<=75 【目星】\nCCB<={目星} 【目星】\n\nCCB<=({POW}*5) 【幸運】\n1d100<={SAN} 【SAN値チェック】\nCCB<=50\n1d3 【ダメージ】\n";
+        let palette = ChatPalette::parse(text, &variables());
+
+        assert_eq!(palette.entries.len(), 6);
+        assert_eq!(
+            palette.entries[4],
+            PaletteEntry {
+                command: "CCB<=50".to_string(),
+                skill: None,
+                target: Some(50),
+            }
+        );
+        assert_eq!(
+            palette.skills(),
+            vec![
+                ("目星".to_string(), 75),
+                ("目星".to_string(), 75),
+                ("幸運".to_string(), 60),
+                ("SAN値チェック".to_string(), 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_unlabeled_entries() {
+        let text = "CCB<=75 目星\nCCB<={POW} \nCCB<=({POW}*5)\nCC<=60";
+        let palette = ChatPalette::parse(text, &variables());
+        let skills: Vec<_> = palette.entries.iter().map(|e| e.skill.clone()).collect();
+        assert_eq!(
+            skills,
+            vec![
+                Some("目星".to_string()),
+                Some("POW".to_string()),
+                None,
+                None
+            ]
+        );
+        assert_eq!(palette.entries[0].dice(), "CCB<=75");
+    }
+
+    #[test]
+    fn test_skill_for() {
+        let text =
+            "CCB<={目星}\nCCB<=60 図書館\nCCB<=50 【回避】\nCCB<=50 【聞き耳】\nCCB<=({POW}*5)";
+        let palette = ChatPalette::parse(text, &variables());
+
+        assert_eq!(
+            palette.skill_for("CCB<={目星} (1D100<=75)", Some(75)),
+            Some("目星".to_string())
+        );
+        assert_eq!(
+            palette.skill_for("ccb<=60 (1D100<=60)", Some(60)),
+            Some("図書館".to_string())
+        );
+        // 目標値だけで探す
+        assert_eq!(
+            palette.skill_for("CC<=75 (1D100<=75)", Some(75)),
+            Some("目星".to_string())
+        );
+        // 同じ目標値の技能が複数あるときは決めない
+        assert_eq!(palette.skill_for("CC<=50 (1D100<=50)", Some(50)), None);
+        assert_eq!(palette.skill_for("CC<=30 (1D100<=30)", Some(30)), None);
+        // 技能判定でないロールは目標値だけでは決めない
+        assert_eq!(palette.skill_for("RESB(12-10) (1d100<=60)", Some(60)), None);
+    }
+
+    #[test]
+    fn test_apply_palettes() {
+        let log = crate::log::Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec![
+                "CCB<=75 (1D100<=75) ＞ 10 ＞ 成功".to_string(),
+                "CCB<=75 【目星+0】 (1D100<=75) ＞ 10 ＞ 成功".to_string(),
+            ],
+            color: String::new(),
+        };
+        let logs = [log];
+        let mut rolls = crate::roll::parse_rolls(&logs);
+        let palettes = HashMap::from([(
+            "イオリ".to_string(),
+            ChatPalette::parse("CCB<=75 【目星】", &HashMap::new()),
+        )]);
+        apply_palettes(&mut rolls, &palettes);
+
+        assert_eq!(rolls[0].skill, Some("目星".to_string()));
+        assert_eq!(rolls[1].skill, Some("目星+0".to_string()));
+    }
+
+    #[test]
+    fn test_apply_palettes_skips_resistance() {
+        let log = crate::log::Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec!["RESB(12-10) (1d100<=60) ＞ 45 ＞ 成功".to_string()],
+            color: String::new(),
+        };
+        let logs = [log];
+        let mut rolls = crate::roll::parse_rolls(&logs);
+        let palettes = HashMap::from([(
+            "イオリ".to_string(),
+            ChatPalette::parse("CCB<=60 【幸運】", &HashMap::new()),
+        )]);
+        apply_palettes(&mut rolls, &palettes);
+
+        assert_eq!(rolls[0].skill, None);
+        assert_eq!(rolls[0].skill_name(), crate::roll::RESISTANCE_SKILL);
+    }
+
+    #[test]
+    fn test_insert_label() {
+        assert_eq!(
+            insert_label("CCB<=75 (1D100<=75)", "目星"),
+            "CCB<=75 【目星】 (1D100<=75)"
+        );
+        assert_eq!(insert_label("CCB<=75", "目星"), "CCB<=75 【目星】");
+    }
+}
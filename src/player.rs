@@ -0,0 +1,99 @@
+use crate::config::Config;
+use crate::log::Log;
+use crate::log_summary::LogSummary;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+// プレイヤーごとの集計
+// 担当した全キャラクターのロールをまとめて数える
+pub struct PlayerSummary<'a> {
+    pub characters: Vec<String>,
+    pub summary: LogSummary<'a>,
+}
+
+impl<'a> PlayerSummary<'a> {
+    pub fn new(characters: Vec<String>, logs: Vec<&'a Log>) -> PlayerSummary<'a> {
+        PlayerSummary {
+            characters,
+            summary: LogSummary::new(logs),
+        }
+    }
+}
+
+impl Display for PlayerSummary<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "キャラクター：{}\n{}",
+            self.characters.join(", "),
+            self.summary
+        )
+    }
+}
+
+// 設定ファイルの[player]に書かれたキャラクターだけを集計する
+pub fn summarize_by_player<'a>(
+    logs: &'a [Log],
+    config: &Config,
+) -> HashMap<String, PlayerSummary<'a>> {
+    let mut characters_by_player: HashMap<&str, Vec<String>> = HashMap::new();
+    for log in logs {
+        let Some(player) = config.players.get(&log.name) else {
+            continue;
+        };
+        let characters = characters_by_player.entry(player).or_default();
+        if !characters.contains(&log.name) {
+            characters.push(log.name.clone());
+        }
+    }
+
+    characters_by_player
+        .into_iter()
+        .map(|(player, characters)| {
+            let logs = logs
+                .iter()
+                .filter(|log| characters.contains(&log.name))
+                .collect();
+            (player.to_string(), PlayerSummary::new(characters, logs))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_summarize_by_player() {
+        let logs = vec![
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 3 ＞ 決定的成功"),
+            log("ソウタ", "CCB<=50 【回避】 (1D100<=50) ＞ 99 ＞ 致命的失敗"),
+            log("イオリ", "CCB<=50 【回避】 (1D100<=50) ＞ 97 ＞ 致命的失敗"),
+            log("ミナ", "CCB<=50 【回避】 (1D100<=50) ＞ 10 ＞ 成功"),
+        ];
+        let mut config = Config::default();
+        config
+            .players
+            .insert("イオリ".to_string(), "山田".to_string());
+        config
+            .players
+            .insert("ソウタ".to_string(), "山田".to_string());
+
+        let summaries = summarize_by_player(&logs, &config);
+        assert_eq!(summaries.len(), 1);
+        let yamada = &summaries["山田"];
+        assert_eq!(yamada.characters, vec!["イオリ", "ソウタ"]);
+        assert_eq!(yamada.summary.criticals.len(), 1);
+        assert_eq!(yamada.summary.fumbles.len(), 2);
+        assert!(yamada.summary.successes.is_empty());
+    }
+}
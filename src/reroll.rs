@@ -0,0 +1,150 @@
+use crate::roll::Roll;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+// 失敗した直後に同じ技能を振り直した疑いのあるロールの組
+pub struct RerollSuspect<'a> {
+    pub name: String,
+    pub failed: Roll<'a>,
+    pub reroll: Roll<'a>,
+}
+
+impl Display for RerollSuspect<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}：\n  {}件目：{}\n  {}件目：{}",
+            self.name,
+            self.failed.index + 1,
+            self.failed.text,
+            self.reroll.index + 1,
+            self.reroll.text
+        )
+    }
+}
+
+// windowは二つのロールの間に許すチャットの件数の差
+pub fn find_rerolls<'a>(
+    rolls_by_name: &HashMap<String, Vec<&Roll<'a>>>,
+    window: usize,
+) -> Vec<RerollSuspect<'a>> {
+    let mut suspects = Vec::new();
+    for (name, rolls) in rolls_by_name {
+        for pair in rolls.windows(2) {
+            let (failed, reroll) = (pair[0], pair[1]);
+            // 繰り返しロールや組み合わせロールは一つのチャットなので振り直しではない
+            if failed.result.is_success()
+                || failed.index == reroll.index
+                || reroll.index - failed.index > window
+            {
+                continue;
+            }
+            if skill_key(failed) == skill_key(reroll) {
+                suspects.push(RerollSuspect {
+                    name: name.clone(),
+                    failed: failed.clone(),
+                    reroll: reroll.clone(),
+                });
+            }
+        }
+    }
+    suspects.sort_by_key(|suspect| suspect.failed.index);
+    suspects
+}
+
+// 【】がなければコマンドそのもので比べる
+fn skill_key(roll: &Roll) -> String {
+    match &roll.skill {
+        Some(_) => roll.skill_name(),
+        None => roll.command.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    fn rolls_by_name<'r, 'a>(rolls: &'r [Roll<'a>]) -> HashMap<String, Vec<&'r Roll<'a>>> {
+        let mut map: HashMap<String, Vec<&Roll>> = HashMap::new();
+        for roll in rolls {
+            map.entry(roll.name().to_string()).or_default().push(roll);
+        }
+        map
+    }
+
+    #[test]
+    fn test_find_rerolls() {
+        let logs = vec![
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 80 ＞ 失敗"),
+            log("ソウタ", "CCB<=25 【目星】 (1D100<=25) ＞ 80 ＞ 失敗"),
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"),
+            log("イオリ", "CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"),
+            log("ソウタ", "CCB<=50 【回避】 (1D100<=50) ＞ 80 ＞ 失敗"),
+            log("ソウタ", "CCB<=50 【回避】 (1D100<=50) ＞ 10 ＞ 成功"),
+        ];
+        let rolls = parse_rolls(&logs);
+        let suspects = find_rerolls(&rolls_by_name(&rolls), 3);
+
+        assert_eq!(suspects.len(), 2);
+        assert_eq!(suspects[0].name, "イオリ");
+        assert_eq!(suspects[0].failed.index, 0);
+        assert_eq!(suspects[0].reroll.index, 2);
+        assert_eq!(suspects[1].name, "ソウタ");
+        assert_eq!(
+            suspects[0].to_string(),
+            "イオリ：\n  1件目：CCB<=25 【目星】 (1D100<=25) ＞ 80 ＞ 失敗\n  3件目：CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"
+        );
+    }
+
+    #[test]
+    fn test_repeated_roll() {
+        let logs = vec![Log {
+            tab: "メイン".to_string(),
+            name: "イオリ".to_string(),
+            texts: vec![
+                "x3 CCB<=50 【幸運】 #1 (1D100<=50) ＞ 70 ＞ 失敗 #2 (1D100<=50) ＞ 20 ＞ 成功 #3 (1D100<=50) ＞ 80 ＞ 失敗".to_string(),
+            ],
+            color: String::new(),
+        }
+        .expand_repeats()];
+        let rolls = parse_rolls(&logs);
+        assert_eq!(rolls.len(), 3);
+        assert!(rolls.iter().all(|roll| roll.index == 0));
+        assert!(find_rerolls(&rolls_by_name(&rolls), 3).is_empty());
+    }
+
+    #[test]
+    fn test_combination_roll() {
+        let logs = vec![log(
+            "イオリ",
+            "CBRB(50,60) (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功",
+        )];
+        let rolls = parse_rolls(&logs);
+        assert_eq!(rolls.len(), 2);
+        assert!(find_rerolls(&rolls_by_name(&rolls), 3).is_empty());
+    }
+
+    #[test]
+    fn test_window() {
+        let logs = vec![
+            log("イオリ", "CCB<=80 (1D100<=80) ＞ 90 ＞ 失敗"),
+            log("KP", "しばらくして"),
+            log("KP", "もう一度"),
+            log("イオリ", "CCB<=80 (1D100<=80) ＞ 10 ＞ 成功"),
+        ];
+        let rolls = parse_rolls(&logs);
+        assert_eq!(find_rerolls(&rolls_by_name(&rolls), 3).len(), 1);
+        assert!(find_rerolls(&rolls_by_name(&rolls), 2).is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::config::Config;
+use crate::log::Log;
+use std::collections::{HashMap, HashSet};
+
+// KPとして扱う名前
+const GM_NAMES: [&str; 8] = [
+    "KP",
+    "GM",
+    "ＫＰ",
+    "ＧＭ",
+    "キーパー",
+    "ゲームマスター",
+    "DL",
+    "ＤＬ",
+];
+// ココフォリアで色を選ばなかったときの文字色
+// 誰でも使うのでNPCの手がかりにならない
+const DEFAULT_COLOR: &str = "#888888";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Pc,
+    Gm,
+    Npc,
+}
+
+impl Role {
+    pub fn to_display_string(&self) -> &str {
+        match self {
+            Role::Pc => "PC",
+            Role::Gm => "KP",
+            Role::Npc => "NPC",
+        }
+    }
+}
+
+// 発言者名ごとの役割
+// 空のときは全員をPCとして扱う
+#[derive(Default)]
+pub struct RoleMap {
+    roles: HashMap<String, Role>,
+}
+
+impl RoleMap {
+    // 優先順位：設定ファイルの指定 > KPらしい名前 > KP用タブ > 文字色
+    pub fn detect(logs: &[Log], config: &Config) -> RoleMap {
+        let mut roles = HashMap::new();
+
+        let names: HashSet<&str> = logs.iter().map(|log| log.name.as_str()).collect();
+        let npc_colors = RoleMap::shared_colors(logs, config.npc_color_threshold);
+
+        for name in names {
+            let role = if config.pc_names.iter().any(|n| n == name) {
+                Role::Pc
+            } else if config.gm_names.iter().any(|n| n == name) {
+                Role::Gm
+            } else if config.npc_names.iter().any(|n| n == name) {
+                Role::Npc
+            } else if GM_NAMES.contains(&name) {
+                Role::Gm
+            } else if RoleMap::speaks_only_in(logs, name, &config.gm_tabs)
+                || RoleMap::speaks_only_in_colors(logs, name, &npc_colors)
+            {
+                Role::Npc
+            } else {
+                Role::Pc
+            };
+            roles.insert(name.to_string(), role);
+        }
+
+        RoleMap { roles }
+    }
+
+    pub fn role(&self, name: &str) -> Role {
+        self.roles.get(name).copied().unwrap_or(Role::Pc)
+    }
+
+    pub fn is_pc(&self, name: &str) -> bool {
+        self.role(name) == Role::Pc
+    }
+
+    // KP・NPCの名前を役割ごとに並べて返す
+    pub fn non_pc_names(&self) -> Vec<(&str, Role)> {
+        let mut names: Vec<_> = self
+            .roles
+            .iter()
+            .filter(|(_, role)| **role != Role::Pc)
+            .map(|(name, role)| (name.as_str(), *role))
+            .collect();
+        names.sort_by_key(|(name, role)| (*role != Role::Gm, *name));
+        names
+    }
+
+    fn speaks_only_in(logs: &[Log], name: &str, tabs: &[String]) -> bool {
+        if tabs.is_empty() {
+            return false;
+        }
+        logs.iter()
+            .filter(|log| log.name == name)
+            .all(|log| tabs.contains(&log.tab))
+    }
+
+    // その名前のすべての発言がNPCの色か
+    // 一度でも別の色で発言していれば、PCが色を借りただけかもしれないのでNPCとしない
+    fn speaks_only_in_colors(logs: &[Log], name: &str, colors: &HashSet<&str>) -> bool {
+        if colors.is_empty() {
+            return false;
+        }
+        logs.iter()
+            .filter(|log| log.name == name)
+            .all(|log| colors.contains(log.color.as_str()))
+    }
+
+    // 多くの名前で使い回されている文字色
+    // KPがNPCを動かすときは同じ色のまま名前だけ変えることが多い
+    fn shared_colors(logs: &[Log], threshold: usize) -> HashSet<&str> {
+        if threshold == 0 {
+            return HashSet::new();
+        }
+        let mut names_by_color: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for log in logs {
+            if log.color.is_empty() || log.color == DEFAULT_COLOR {
+                continue;
+            }
+            names_by_color
+                .entry(log.color.as_str())
+                .or_default()
+                .insert(log.name.as_str());
+        }
+        names_by_color
+            .into_iter()
+            .filter(|(_, names)| names.len() >= threshold)
+            .map(|(color, _)| color)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(tab: &str, name: &str, color: &str) -> Log {
+        Log {
+            tab: tab.to_string(),
+            name: name.to_string(),
+            texts: vec![],
+            color: color.to_string(),
+        }
+    }
+
+    fn config_with_threshold(threshold: usize) -> Config {
+        Config {
+            npc_color_threshold: threshold,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_detect_by_heuristics() {
+        let logs = vec![
+            log("main", "KP", "#9c27b0"),
+            log("main", "イオリ", "#e91e63"),
+            log("main", "警官", "#9c27b0"),
+            log("main", "店主", "#9c27b0"),
+            log("main", "ソウタ", "#2196f3"),
+        ];
+        let roles = RoleMap::detect(&logs, &config_with_threshold(3));
+        assert_eq!(roles.role("KP"), Role::Gm);
+        assert_eq!(roles.role("警官"), Role::Npc);
+        assert_eq!(roles.role("店主"), Role::Npc);
+        assert!(roles.is_pc("イオリ"));
+        assert!(roles.is_pc("ソウタ"));
+        assert_eq!(
+            roles.non_pc_names(),
+            vec![("KP", Role::Gm), ("店主", Role::Npc), ("警官", Role::Npc)]
+        );
+    }
+
+    #[test]
+    fn test_detect_by_config() {
+        let logs = vec![
+            log("main", "進行役", ""),
+            log("KP用", "怪物", ""),
+            log("main", "ミナ", ""),
+            log("KP用", "ミナ", ""),
+        ];
+        let config = Config {
+            gm_names: vec!["進行役".to_string()],
+            gm_tabs: vec!["KP用".to_string()],
+            ..Config::default()
+        };
+        let roles = RoleMap::detect(&logs, &config);
+        assert_eq!(roles.role("進行役"), Role::Gm);
+        assert_eq!(roles.role("怪物"), Role::Npc);
+        assert!(roles.is_pc("ミナ"));
+    }
+
+    #[test]
+    fn test_pc_list_overrides_color() {
+        let logs = vec![
+            log("main", "A", "#000"),
+            log("main", "B", "#000"),
+            log("main", "C", "#000"),
+        ];
+        let config = Config {
+            pc_names: vec!["A".to_string()],
+            ..config_with_threshold(3)
+        };
+        let roles = RoleMap::detect(&logs, &config);
+        assert!(roles.is_pc("A"));
+        assert_eq!(roles.role("B"), Role::Npc);
+    }
+
+    #[test]
+    fn test_color_is_opt_in() {
+        let logs = vec![
+            log("main", "A", "#000"),
+            log("main", "B", "#000"),
+            log("main", "C", "#000"),
+        ];
+        let roles = RoleMap::detect(&logs, &Config::default());
+        assert!(roles.is_pc("A"));
+        assert!(roles.is_pc("B"));
+        assert!(roles.is_pc("C"));
+    }
+
+    #[test]
+    fn test_default_color_is_ignored() {
+        let logs = vec![
+            log("main", "A", "#888888"),
+            log("main", "B", "#888888"),
+            log("main", "C", "#888888"),
+            log("main", "D", ""),
+            log("main", "E", ""),
+            log("main", "F", ""),
+        ];
+        let roles = RoleMap::detect(&logs, &config_with_threshold(3));
+        assert!(roles.non_pc_names().is_empty());
+    }
+
+    #[test]
+    fn test_all_messages_must_share_color() {
+        let logs = vec![
+            log("main", "警官", "#000"),
+            log("main", "店主", "#000"),
+            log("main", "イオリ", "#e91e63"),
+            // PCがNPCの色を一度だけ借りた
+            log("main", "イオリ", "#000"),
+        ];
+        let roles = RoleMap::detect(&logs, &config_with_threshold(3));
+        assert!(roles.is_pc("イオリ"));
+        assert_eq!(roles.role("警官"), Role::Npc);
+        assert_eq!(roles.role("店主"), Role::Npc);
+    }
+}
@@ -0,0 +1,593 @@
+use crate::alias::strip_parentheticals;
+use crate::log::Log;
+
+// 【】のないロールの技能名
+pub const NO_SKILL: &str = "（技能名なし）";
+pub const RESISTANCE_SKILL: &str = "抵抗ロール";
+// 結果が隠されたシークレットダイスの表示
+pub const SECRET_PLACEHOLDER: &str = "シークレットダイス";
+// シークレットダイスの「S」の後ろに来るコマンド
+const DICE_COMMANDS: [&str; 4] = ["CC", "CBR", "RES", "CHOICE"];
+// 後ろに回数が続く繰り返しコマンド
+const REPEAT_COMMANDS: [&str; 2] = ["X", "REP"];
+// 専門分野ごとに別の技能になるもの
+// 「芸術(絵画)」と「芸術(歌唱)」は別々に数える
+const SPECIALIZED_SKILLS: [&str; 7] = [
+    "芸術",
+    "製作",
+    "運転",
+    "操縦",
+    "ほかの言語",
+    "他の言語",
+    "母国語",
+];
+// 能力値
+// 能力値ロールは「POW×5」のように能力値そのものとは違う目標値で振る
+pub(crate) const CHARACTERISTICS: [&str; 8] =
+    ["STR", "CON", "POW", "DEX", "APP", "SIZ", "INT", "EDU"];
+// セッション中に値が変わるのが普通なもの
+pub(crate) const VARIABLE_SKILLS: [&str; 3] = ["SAN値チェック", "SANチェック", "正気度ロール"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollKind {
+    Normal,
+    // CBRB(50,60)の片方
+    Combination,
+    // RESB(12-10)
+    Resistance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RollResult {
+    Critical,
+    Success,
+    Failure,
+    Fumble,
+}
+
+impl RollResult {
+    // LogSummary::newと同じ基準で判定する
+    pub fn from_text(text: &str) -> Option<RollResult> {
+        if text.contains("決定的成功") {
+            Some(RollResult::Critical)
+        } else if text.contains("致命的失敗") {
+            Some(RollResult::Fumble)
+        } else if text.contains("成功") || text.contains("スペシャル") {
+            Some(RollResult::Success)
+        } else if text.contains("失敗") {
+            Some(RollResult::Failure)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_display_string(&self) -> &str {
+        match self {
+            RollResult::Critical => "クリティカル",
+            RollResult::Success => "成功",
+            RollResult::Failure => "失敗",
+            RollResult::Fumble => "ファンブル",
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, RollResult::Critical | RollResult::Success)
+    }
+}
+
+// 判定ロール一回分
+// 例：CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功
+#[derive(Debug, Clone)]
+pub struct Roll<'a> {
+    pub log: &'a Log,
+    // get_logsで得たVec<Log>の中での位置
+    pub index: usize,
+    pub text: &'a str,
+    // 「＞」より前の部分
+    pub command: String,
+    // 【】で囲まれた技能名
+    pub skill: Option<String>,
+    pub target: Option<u32>,
+    pub value: Option<u32>,
+    pub result: RollResult,
+    pub kind: RollKind,
+    // 組み合わせロールの何番目の技能か（それ以外は0）
+    // 1D100は一度しか振っていないので、出目の集計には0番目だけを使う
+    pub part: usize,
+    // 「S CCB<=50」のようなシークレットダイス
+    pub secret: bool,
+}
+
+impl<'a> Roll<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &'a str) -> Option<Roll<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        if segments.len() < 2 {
+            return None;
+        }
+        let result = RollResult::from_text(segments[segments.len() - 1])?;
+        let command = segments[0].to_string();
+
+        // 出目は結果の直前にある
+        let value = if segments.len() >= 3 {
+            segments[segments.len() - 2].parse().ok()
+        } else {
+            None
+        };
+
+        let kind = if is_resistance_roll(text) {
+            RollKind::Resistance
+        } else {
+            RollKind::Normal
+        };
+        Some(Roll {
+            log,
+            index,
+            text,
+            skill: parse_skill(&command),
+            target: parse_target(&command),
+            value,
+            command,
+            result,
+            kind,
+            part: 0,
+            secret: is_secret_roll(text),
+        })
+    }
+
+    // 組み合わせロールは技能ごとのロールに分ける
+    // 例：CBRB(50,60) (1d100<=50,60) ＞ 22[成功,失敗] ＞ 部分的成功
+    pub fn parse_combination(log: &'a Log, index: usize, text: &'a str) -> Option<Vec<Roll<'a>>> {
+        let results = combination_results(text)?;
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        let command = segments[0].to_string();
+        let detail = segments[segments.len() - 2];
+        let value = detail[..detail.find('[')?].trim().parse().ok();
+
+        let start = command.rfind("<=")? + "<=".len();
+        let targets: Vec<Option<u32>> = command[start..]
+            .split(',')
+            .map(|target| {
+                let digits: String = target
+                    .trim()
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                digits.parse().ok()
+            })
+            .collect();
+        // 【目星,聞き耳】のように技能名も並べて書けば対応づける
+        let skills: Vec<String> = parse_skill(&command)
+            .map(|skill| {
+                skill
+                    .split([',', '、', '，'])
+                    .map(|s| s.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let rolls = results
+            .into_iter()
+            .enumerate()
+            .map(|(i, result)| Roll {
+                log,
+                index,
+                text,
+                command: command.clone(),
+                skill: match skills.len() {
+                    0 => None,
+                    n if n == targets.len() => Some(skills[i].clone()),
+                    _ => Some(skills.join(",")),
+                },
+                target: targets.get(i).copied().flatten(),
+                value,
+                result,
+                kind: RollKind::Combination,
+                part: i,
+                secret: is_secret_roll(text),
+            })
+            .collect();
+        Some(rolls)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.log.name
+    }
+
+    // 集計用に正規化した技能名
+    pub fn skill_name(&self) -> String {
+        match &self.skill {
+            Some(skill) => normalize_skill(skill),
+            None if self.kind == RollKind::Resistance => RESISTANCE_SKILL.to_string(),
+            None => NO_SKILL.to_string(),
+        }
+    }
+
+    // 一つのダイスの最初のロールか（組み合わせロールの2番目以降でないか）
+    pub fn is_first_part(&self) -> bool {
+        self.part == 0
+    }
+
+    // 1D100のロールか
+    pub fn is_d100(&self) -> bool {
+        let command = self.command.to_uppercase();
+        command.contains("1D100") || dice_command(&command).starts_with("CC")
+    }
+}
+
+pub fn parse_rolls(logs: &[Log]) -> Vec<Roll<'_>> {
+    let mut rolls = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(combination) = Roll::parse_combination(log, index, text) {
+                rolls.extend(combination);
+            } else if let Some(roll) = Roll::parse(log, index, text) {
+                rolls.push(roll);
+            }
+        }
+    }
+    rolls
+}
+
+// 「＞」より前の部分
+fn command_of(text: &str) -> &str {
+    text.split('＞').next().unwrap_or("").trim()
+}
+
+// 大文字にしてシークレットダイスの「S」を取り除いたコマンド
+fn dice_command(text: &str) -> String {
+    let command = command_of(text).to_uppercase();
+    match strip_secret_prefix(&command) {
+        Some(rest) => rest.to_string(),
+        None => command,
+    }
+}
+
+// 「S」の後ろがBCDiceのコマンドになっているときだけシークレットダイスとみなす
+// 「SAN値チェック」「S1の…」のような普通の発言を拾わないようにする
+pub(crate) fn strip_secret_prefix(command: &str) -> Option<&str> {
+    let rest = command.strip_prefix('S')?.trim_start();
+    if is_dice_command(rest) {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+// 大文字にしたコマンドがCCB・x3・2D6・10DXなどで始まるか
+fn is_dice_command(command: &str) -> bool {
+    if DICE_COMMANDS.iter().any(|dice| command.starts_with(dice)) {
+        return true;
+    }
+    if REPEAT_COMMANDS.iter().any(|repeat| {
+        command
+            .strip_prefix(repeat)
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+    }) {
+        return true;
+    }
+    // 2D6・1D100・10DX・2DMのように、個数・「D」・面数（またはDX・DM）が続く
+    let rest = command.trim_start_matches(|c: char| c.is_ascii_digit());
+    rest.len() < command.len()
+        && rest.strip_prefix('D').is_some_and(|rest| {
+            rest.starts_with(|c: char| c.is_ascii_digit() || c == 'X' || c == 'M')
+        })
+}
+
+// 結果の見えているシークレットダイスか
+pub fn is_secret_roll(text: &str) -> bool {
+    text.contains('＞') && strip_secret_prefix(&command_of(text).to_uppercase()).is_some()
+}
+
+// 結果が隠されて「シークレットダイス」とだけ表示されたものか
+pub fn is_secret_placeholder(text: &str) -> bool {
+    text.contains(SECRET_PLACEHOLDER) && !text.contains('＞')
+}
+
+// 対抗ロール（RES(12-10)・RESB(12-10)）か
+pub fn is_resistance_roll(text: &str) -> bool {
+    text.contains('＞') && dice_command(text).starts_with("RES")
+}
+
+// 組み合わせロール（CBR(50,60)・CBRB(50,60)）の技能ごとの結果
+pub fn combination_results(text: &str) -> Option<Vec<RollResult>> {
+    if !dice_command(text).starts_with("CBR") {
+        return None;
+    }
+    let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+    if segments.len() < 3 {
+        return None;
+    }
+    let detail = segments[segments.len() - 2];
+    let start = detail.find('[')? + '['.len_utf8();
+    let end = start + detail[start..].find(']')?;
+    detail[start..end]
+        .split(',')
+        .map(|result| RollResult::from_text(result.trim()))
+        .collect()
+}
+
+// 「x3 CCB<=50」「rep3 CCB<=50」の繰り返しロールを1回ずつに分ける
+// 例：x3 CCB<=50 #1 (1D100<=50) ＞ 22 ＞ 成功 #2 (1D100<=50) ＞ 70 ＞ 失敗 ...
+// ->「CCB<=50 (1D100<=50) ＞ 22 ＞ 成功」「CCB<=50 (1D100<=50) ＞ 70 ＞ 失敗」...
+// シークレットダイス「Sx3 CCB<=50 ...」は1回ごとに「S CCB<=50 ...」にする
+pub fn expand_repeated_roll(text: &str) -> Option<Vec<String>> {
+    let text = text.trim_start();
+    let (prefix, text) = match text.strip_prefix(['S', 's']) {
+        Some(rest) => ("S ", rest),
+        None => ("", text),
+    };
+    let rest = strip_repeat_prefix(text)?;
+    let (command, results) = rest.split_once('#')?;
+    let command = command.trim();
+    let texts: Vec<String> = results
+        .split('#')
+        .map(|result| {
+            result
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim()
+        })
+        .filter(|result| result.contains('＞'))
+        .map(|result| format!("{}{} {}", prefix, command, result))
+        .collect();
+    if texts.is_empty() {
+        None
+    } else {
+        Some(texts)
+    }
+}
+
+// 「x3 」「rep3 」「repeat3 」を取り除く
+fn strip_repeat_prefix(text: &str) -> Option<&str> {
+    let text = text.trim_start();
+    let lower = text.to_ascii_lowercase();
+    let prefix = ["repeat", "rep", "x"]
+        .into_iter()
+        .find(|prefix| lower.starts_with(prefix))?;
+    let rest = &text[prefix.len()..];
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let rest = &rest[digits..];
+    if digits == 0 || !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(rest.trim_start())
+}
+
+// 「ＳＡＮ値チェック」「目星 (補正あり)」「目星+10」のような表記ゆれをそろえる
+pub fn normalize_skill(skill: &str) -> String {
+    // 全角英数字を半角にして空白を取り除く
+    let skill: String = skill
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '！'..='～' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect();
+    let skill = strip_notes(&skill);
+
+    // 末尾の補正値を取り除く
+    let trimmed = skill.trim_end_matches(|c: char| "+-*/×0123456789".contains(c));
+    if trimmed.is_empty() {
+        skill
+    } else {
+        trimmed.to_string()
+    }
+}
+
+// 「目星(補正あり)」のような注記の括弧書きを取り除く
+// 「芸術(絵画)」のような専門分野は残す
+fn strip_notes(skill: &str) -> String {
+    if let Some((base, rest)) = skill.split_once('(') {
+        if let Some((specialty, rest)) = rest.split_once(')') {
+            if SPECIALIZED_SKILLS.contains(&base) && !specialty.is_empty() {
+                // 残りがすべて括弧書きのときはそのまま返ってくるので空にする
+                let rest = strip_parentheticals(rest);
+                let rest = if rest.starts_with('(') { "" } else { &rest };
+                return format!("{}({}){}", base, specialty, rest);
+            }
+        }
+    }
+    strip_parentheticals(skill)
+}
+
+pub(crate) fn parse_skill(command: &str) -> Option<String> {
+    let start = command.find('【')? + '【'.len_utf8();
+    let end = start + command[start..].find('】')?;
+    let skill = command[start..end].trim();
+    if skill.is_empty() {
+        None
+    } else {
+        Some(skill.to_string())
+    }
+}
+
+// 「(1D100<=25)」のように展開済みの目標値が後ろにあるので最後の「<=」を使う
+pub(crate) fn parse_target(command: &str) -> Option<u32> {
+    let start = command.rfind("<=")? + "<=".len();
+    let digits: String = command[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "PC1".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_coc6_roll() {
+        let log = log(&["CCB<=25 【目星】 (1D100<=25) ＞ 10 ＞ 成功"]);
+        let roll = Roll::parse(&log, 3, &log.texts[0]).unwrap();
+        assert_eq!(roll.index, 3);
+        assert_eq!(roll.command, "CCB<=25 【目星】 (1D100<=25)");
+        assert_eq!(roll.skill, Some("目星".to_string()));
+        assert_eq!(roll.target, Some(25));
+        assert_eq!(roll.value, Some(10));
+        assert_eq!(roll.result, RollResult::Success);
+        assert!(roll.is_d100());
+    }
+
+    #[test]
+    fn test_parse_results() {
+        let log = log(&[
+            "CCB<=70 (1D100<=70) ＞ 3 ＞ 決定的成功/スペシャル",
+            "CCB<=50 【回避】 (1D100<=50) ＞ 99 ＞ 致命的失敗",
+            "CCB<={目星} (1D100<=80) ＞ 81 ＞ 失敗",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        assert_eq!(rolls.len(), 3);
+        assert_eq!(rolls[0].result, RollResult::Critical);
+        assert_eq!(rolls[0].skill, None);
+        assert_eq!(rolls[1].result, RollResult::Fumble);
+        assert_eq!(rolls[2].result, RollResult::Failure);
+        assert_eq!(rolls[2].target, Some(80));
+    }
+
+    #[test]
+    fn test_normalize_skill() {
+        assert_eq!(normalize_skill("目星"), "目星");
+        assert_eq!(normalize_skill(" 目星 (補正あり)"), "目星");
+        assert_eq!(normalize_skill("目星+10"), "目星");
+        assert_eq!(normalize_skill("ＳＡＮ値チェック"), "SAN値チェック");
+        assert_eq!(normalize_skill("100"), "100");
+        assert_eq!(normalize_skill("芸術（絵画）"), "芸術(絵画)");
+        assert_eq!(normalize_skill("芸術(絵画)+10"), "芸術(絵画)");
+        assert_eq!(normalize_skill("芸術(絵画)(補正あり)"), "芸術(絵画)");
+        assert_eq!(normalize_skill("ほかの言語（英語）"), "ほかの言語(英語)");
+
+        let log = log(&["CCB<=80 (1D100<=80) ＞ 81 ＞ 失敗"]);
+        let roll = Roll::parse(&log, 0, &log.texts[0]).unwrap();
+        assert_eq!(roll.skill_name(), NO_SKILL);
+    }
+
+    #[test]
+    fn test_parse_non_rolls() {
+        let log = log(&["普通のテキスト", "1d10 (1D10) ＞ 8", "＞ 成功"]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        assert_eq!(rolls.len(), 1);
+        assert_eq!(rolls[0].value, None);
+        assert_eq!(rolls[0].target, None);
+        assert!(!rolls[0].is_d100());
+    }
+
+    #[test]
+    fn test_parse_combination() {
+        let log = log(&[
+            "CBRB(50,60) 【目星,聞き耳】 (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功",
+            "CBRB(50,60) (1d100<=50,60) ＞ 3[決定的成功/スペシャル,決定的成功/スペシャル] ＞ 成功",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        assert_eq!(rolls.len(), 4);
+        assert_eq!(rolls[0].skill, Some("目星".to_string()));
+        assert_eq!(rolls[0].target, Some(50));
+        assert_eq!(rolls[0].value, Some(55));
+        assert_eq!(rolls[0].result, RollResult::Failure);
+        assert_eq!(rolls[0].kind, RollKind::Combination);
+        assert_eq!(rolls[1].skill, Some("聞き耳".to_string()));
+        assert_eq!(rolls[1].target, Some(60));
+        assert_eq!(rolls[1].result, RollResult::Success);
+        assert_eq!(rolls[2].skill, None);
+        assert_eq!(rolls[3].result, RollResult::Critical);
+        assert!(rolls[3].is_d100());
+    }
+
+    #[test]
+    fn test_parse_resistance() {
+        let log = log(&[
+            "RESB(12-10) (1d100<=60) ＞ 45 ＞ 成功",
+            "CCB<=50 【抵抗】 (1D100<=50) ＞ 45 ＞ 成功",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        assert_eq!(rolls[0].kind, RollKind::Resistance);
+        assert_eq!(rolls[0].target, Some(60));
+        assert_eq!(rolls[0].skill_name(), RESISTANCE_SKILL);
+        assert_eq!(rolls[1].kind, RollKind::Normal);
+        assert!(!is_resistance_roll("RESB(12-10)"));
+    }
+
+    #[test]
+    fn test_combination_results() {
+        assert_eq!(
+            combination_results("CBRB(50,60) (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功"),
+            Some(vec![RollResult::Failure, RollResult::Success])
+        );
+        assert_eq!(
+            combination_results("CCB<=50 (1D100<=50) ＞ 45 ＞ 成功"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_expand_repeated_roll() {
+        assert_eq!(
+            expand_repeated_roll(
+                "x3 CCB<=50 【目星】 #1 (1D100<=50) ＞ 22 ＞ 成功 #2 (1D100<=50) ＞ 70 ＞ 失敗 #3 (1D100<=50) ＞ 99 ＞ 致命的失敗"
+            ),
+            Some(vec![
+                "CCB<=50 【目星】 (1D100<=50) ＞ 22 ＞ 成功".to_string(),
+                "CCB<=50 【目星】 (1D100<=50) ＞ 70 ＞ 失敗".to_string(),
+                "CCB<=50 【目星】 (1D100<=50) ＞ 99 ＞ 致命的失敗".to_string(),
+            ])
+        );
+        assert_eq!(
+            expand_repeated_roll("rep2 1d100#1(1D100) ＞ 45#2(1D100) ＞ 3"),
+            Some(vec![
+                "1d100 (1D100) ＞ 45".to_string(),
+                "1d100 (1D100) ＞ 3".to_string(),
+            ])
+        );
+        assert_eq!(
+            expand_repeated_roll("CCB<=50 (1D100<=50) ＞ 22 ＞ 成功"),
+            None
+        );
+        assert_eq!(
+            expand_repeated_roll(
+                "Sx2 CCB<=50 #1 (1D100<=50) ＞ 22 ＞ 成功 #2 (1D100<=50) ＞ 70 ＞ 失敗"
+            ),
+            Some(vec![
+                "S CCB<=50 (1D100<=50) ＞ 22 ＞ 成功".to_string(),
+                "S CCB<=50 (1D100<=50) ＞ 70 ＞ 失敗".to_string(),
+            ])
+        );
+        assert_eq!(expand_repeated_roll("x2 ありがとう"), None);
+        assert_eq!(expand_repeated_roll("Sのx2 ＞ 2"), None);
+        assert_eq!(expand_repeated_roll("xの値は #1 ＞ 2"), None);
+    }
+
+    #[test]
+    fn test_secret_rolls() {
+        let log = log(&[
+            "S CCB<=50 【目星】 (1D100<=50) ＞ 22 ＞ 成功",
+            "SCBRB(50,60) (1d100<=50,60) ＞ 55[失敗,成功] ＞ 部分的成功",
+            "SAN値チェック (1D100<=50) ＞ 22 ＞ 成功",
+            "CCB<=50 (1D100<=50) ＞ 22 ＞ 成功",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        let secrets: Vec<bool> = rolls.iter().map(|roll| roll.secret).collect();
+        assert_eq!(secrets, vec![true, true, true, false, false]);
+        assert!(rolls[0].is_d100());
+        assert_eq!(rolls[1].kind, RollKind::Combination);
+
+        assert!(is_secret_roll("S2D6 (2D6) ＞ 7"));
+        assert!(is_secret_roll("Sx3 CCB<=50 #1 (1D100<=50) ＞ 22 ＞ 成功"));
+        assert!(is_secret_roll("S10DX+2@10 (10DX10+2) ＞ 8 ＞ 10"));
+        assert!(!is_secret_roll("S1の扉を開ける ＞ 開いた"));
+        assert!(!is_secret_roll("SDカードを拾う ＞ 拾った"));
+        assert!(!is_secret_roll("SXは ＞ 10"));
+
+        assert!(is_secret_placeholder("シークレットダイス"));
+        assert!(!is_secret_placeholder(
+            "S CCB<=50 (1D100<=50) ＞ 22 ＞ 成功"
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::log::Log;
+use crate::roll::{is_secret_placeholder, is_secret_roll, parse_rolls, Roll};
+use std::fmt::{Display, Formatter};
+
+// KP用のシークレットダイスの一覧
+pub struct SecretReport<'a> {
+    // 結果の分かるもの
+    pub rolls: Vec<Roll<'a>>,
+    // 「シークレットダイス」とだけ表示されたものの発言者とログ内の位置
+    pub placeholders: Vec<(String, usize)>,
+}
+
+impl SecretReport<'_> {
+    pub fn new(logs: &[Log]) -> SecretReport<'_> {
+        let rolls = parse_rolls(logs)
+            .into_iter()
+            .filter(|roll| roll.secret)
+            .collect();
+        let placeholders = logs
+            .iter()
+            .enumerate()
+            .filter(|(_, log)| log.texts.iter().any(|text| is_secret_placeholder(text)))
+            .map(|(index, log)| (log.name.clone(), index))
+            .collect();
+        SecretReport {
+            rolls,
+            placeholders,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rolls.is_empty() && self.placeholders.is_empty()
+    }
+}
+
+impl Display for SecretReport<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for roll in &self.rolls {
+            writeln!(f, "{}件目 {}：{}", roll.index + 1, roll.name(), roll.text)?;
+        }
+        if !self.placeholders.is_empty() {
+            let positions = self
+                .placeholders
+                .iter()
+                .map(|(name, index)| format!("{}件目 {}", index + 1, name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(f, "結果の分からないもの：{}", positions)?;
+        }
+        Ok(())
+    }
+}
+
+// シークレットダイスを取り除いたログ
+// ロールの位置が変わらないようにチャット自体は残す
+pub fn hide_secrets(logs: &[Log]) -> Vec<Log> {
+    logs.iter()
+        .map(|log| Log {
+            texts: log
+                .texts
+                .iter()
+                .filter(|text| !is_secret_roll(text) && !is_secret_placeholder(text))
+                .cloned()
+                .collect(),
+            ..log.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    fn logs() -> Vec<Log> {
+        vec![
+            log("イオリ", "CCB<=50 【目星】 (1D100<=50) ＞ 22 ＞ 成功"),
+            log("KP", "S CCB<=60 【聞き耳】 (1D100<=60) ＞ 80 ＞ 失敗"),
+            log("ソウタ", "シークレットダイス"),
+        ]
+    }
+
+    #[test]
+    fn test_secret_report() {
+        let logs = logs();
+        let report = SecretReport::new(&logs);
+        assert!(!report.is_empty());
+        assert_eq!(
+            report.to_string(),
+            "2件目 KP：S CCB<=60 【聞き耳】 (1D100<=60) ＞ 80 ＞ 失敗\n結果の分からないもの：3件目 ソウタ\n"
+        );
+        assert!(SecretReport::new(&logs[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_secret_repeat_roll() {
+        let logs = vec![log(
+            "KP",
+            "Sx3 CCB<=50 #1 (1D100<=50) ＞ 22 ＞ 成功 #2 (1D100<=50) ＞ 70 ＞ 失敗 #3 (1D100<=50) ＞ 99 ＞ 致命的失敗",
+        )
+        .expand_repeats()];
+        let rolls = parse_rolls(&logs);
+        assert_eq!(rolls.len(), 3);
+        assert!(rolls.iter().all(|roll| roll.secret));
+
+        let report = SecretReport::new(&logs);
+        assert_eq!(report.to_string().lines().count(), 3);
+        assert!(hide_secrets(&logs)[0].texts.is_empty());
+    }
+
+    #[test]
+    fn test_hide_secrets() {
+        let logs = hide_secrets(&logs());
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[0].texts.len(), 1);
+        assert!(logs[1].texts.is_empty());
+        assert!(logs[2].texts.is_empty());
+    }
+}
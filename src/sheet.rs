@@ -0,0 +1,258 @@
+use crate::consistency::has_modifier;
+use crate::error::{MyError, ParseError};
+use crate::palette::ChatPalette;
+use crate::roll::{normalize_skill, Roll, CHARACTERISTICS, VARIABLE_SKILLS};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::Read;
+
+// ccfoliaの「クリップボードにコピー」で出力されるキャラクターのJSON
+// {"kind":"character","data":{"name":"イオリ","status":[...],"params":[...],"commands":"..."}}
+#[derive(Debug, Clone)]
+pub struct CharacterSheet {
+    pub name: String,
+    // HP・MP・SANなど
+    pub status: Vec<(String, i64)>,
+    // STR・目星など
+    pub params: Vec<(String, i64)>,
+    pub palette: ChatPalette,
+    // 技能名（正規化済み） -> 技能値
+    pub skills: BTreeMap<String, u32>,
+}
+
+impl CharacterSheet {
+    pub fn load(filename: &str) -> Result<CharacterSheet, MyError> {
+        let mut file = File::open(filename)?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        Ok(CharacterSheet::parse(&text)?)
+    }
+
+    pub fn parse(text: &str) -> Result<CharacterSheet, ParseError> {
+        let json: Value = serde_json::from_str(text).map_err(|e| ParseError {
+            string: format!("キャラクターのJSONが読めませんでした：{}", e),
+        })?;
+        // "data"の中身だけを貼り付けたものも受け付ける
+        let data = json.get("data").unwrap_or(&json);
+        let name = data
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| ParseError {
+                string: "キャラクターのJSONに名前がありません".to_string(),
+            })?
+            .to_string();
+
+        let status = labeled_values(data.get("status"));
+        let params = labeled_values(data.get("params"));
+        let variables = to_variables(&status, &params);
+        let commands = data.get("commands").and_then(Value::as_str).unwrap_or("");
+        let palette = ChatPalette::parse(commands, &variables);
+
+        // パレットにない技能はパラメータの値を使う
+        // 能力値はそのまま目標値にならないので技能値として扱わない
+        let mut skills: BTreeMap<String, u32> = params
+            .iter()
+            .map(|(label, value)| (normalize_skill(label), *value))
+            .filter(|(skill, _)| !CHARACTERISTICS.contains(&skill.as_str()))
+            .filter_map(|(skill, value)| Some((skill, u32::try_from(value).ok()?)))
+            .collect();
+        skills.extend(palette.skills());
+
+        Ok(CharacterSheet {
+            name,
+            status,
+            params,
+            palette,
+            skills,
+        })
+    }
+
+    pub fn skill_value(&self, skill: &str) -> Option<u32> {
+        self.skills.get(&normalize_skill(skill)).copied()
+    }
+
+    // チャットパレットの{名前}の参照先
+    pub fn variables(&self) -> HashMap<String, i64> {
+        to_variables(&self.status, &self.params)
+    }
+}
+
+fn to_variables(status: &[(String, i64)], params: &[(String, i64)]) -> HashMap<String, i64> {
+    status.iter().chain(params).cloned().collect()
+}
+
+// [{"label":"HP","value":10}, ...]
+// paramsのvalueは文字列なので数値に直す
+fn labeled_values(value: Option<&Value>) -> Vec<(String, i64)> {
+    let Some(items) = value.and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let label = item.get("label")?.as_str()?.trim();
+            let value = match item.get("value")? {
+                Value::Number(number) => number.as_i64()?,
+                Value::String(string) => string.trim().parse().ok()?,
+                _ => return None,
+            };
+            if label.is_empty() {
+                None
+            } else {
+                Some((label.to_string(), value))
+            }
+        })
+        .collect()
+}
+
+// シートの技能値と違う目標値で振ったロール
+pub struct SheetMismatch<'a> {
+    pub name: String,
+    pub skill: String,
+    pub sheet_value: u32,
+    pub roll: Roll<'a>,
+}
+
+impl Display for SheetMismatch<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}【{}】：シート {} / ロール {}（{}件目）",
+            self.name,
+            self.skill,
+            self.sheet_value,
+            self.roll.target.unwrap_or_default(),
+            self.roll.index + 1
+        )
+    }
+}
+
+pub fn find_sheet_mismatches<'a>(
+    sheets: &HashMap<String, CharacterSheet>,
+    rolls_by_name: &HashMap<String, Vec<&Roll<'a>>>,
+) -> Vec<SheetMismatch<'a>> {
+    let mut mismatches = Vec::new();
+    for (name, rolls) in rolls_by_name {
+        let Some(sheet) = sheets.get(name) else {
+            continue;
+        };
+        for roll in rolls {
+            let (Some(skill), Some(target)) = (&roll.skill, roll.target) else {
+                continue;
+            };
+            // 補正つきのロールやSAN値チェックは目標値が変わって当然なので除く
+            let normalized = normalize_skill(skill);
+            if has_modifier(skill) || VARIABLE_SKILLS.contains(&normalized.as_str()) {
+                continue;
+            }
+            let skill = normalized;
+            match sheet.skill_value(&skill) {
+                Some(sheet_value) if sheet_value != target => mismatches.push(SheetMismatch {
+                    name: name.clone(),
+                    skill,
+                    sheet_value,
+                    roll: (*roll).clone(),
+                }),
+                _ => {}
+            }
+        }
+    }
+    mismatches.sort_by_key(|mismatch| mismatch.roll.index);
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    const SHEET: &str = r#"{
+        "kind": "character",
+        "data": {
+            "name": "イオリ",
+            "status": [
+                {"label": "HP", "value": 11, "max": 11},
+                {"label": "SAN", "value": 60, "max": 99}
+            ],
+            "params": [
+                {"label": "POW", "value": "12"},
+                {"label": "目星", "value": "75"},
+                {"label": "職業", "value": "探偵"}
+            ],
+            "commands": "CCB<={目星} 【目星】\nCCB<=({POW}*5) 【幸運】\nCCB<=60 【図書館】\n1d100<={SAN} 【SAN値チェック】"
+        }
+    }"#;
+
+    fn log(name: &str, text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: name.to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_sheet() {
+        let sheet = CharacterSheet::parse(SHEET).unwrap();
+        assert_eq!(sheet.name, "イオリ");
+        assert_eq!(
+            sheet.status,
+            vec![("HP".to_string(), 11), ("SAN".to_string(), 60)]
+        );
+        assert_eq!(
+            sheet.params,
+            vec![("POW".to_string(), 12), ("目星".to_string(), 75)]
+        );
+        assert_eq!(sheet.skill_value("目星"), Some(75));
+        assert_eq!(sheet.skill_value("幸運"), Some(60));
+        assert_eq!(sheet.skill_value("図書館"), Some(60));
+        assert_eq!(sheet.skill_value("ＰＯＷ"), None);
+        assert_eq!(sheet.skill_value("回避"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(CharacterSheet::parse("not json").is_err());
+        assert!(CharacterSheet::parse(r#"{"kind":"character","data":{}}"#).is_err());
+        // dataだけでもよい
+        let sheet = CharacterSheet::parse(r#"{"name":"ソウタ"}"#).unwrap();
+        assert_eq!(sheet.name, "ソウタ");
+        assert!(sheet.skills.is_empty());
+    }
+
+    #[test]
+    fn test_find_sheet_mismatches() {
+        let sheets = HashMap::from([("イオリ".to_string(), CharacterSheet::parse(SHEET).unwrap())]);
+        let logs = vec![
+            log("イオリ", "CCB<=75 【目星】 (1D100<=75) ＞ 10 ＞ 成功"),
+            log("イオリ", "CCB<=85 【目星】 (1D100<=85) ＞ 10 ＞ 成功"),
+            log("イオリ", "CCB<=85 【目星+10】 (1D100<=85) ＞ 10 ＞ 成功"),
+            log("イオリ", "CCB<=50 【回避】 (1D100<=50) ＞ 10 ＞ 成功"),
+            log(
+                "イオリ",
+                "CCB<=52 【SAN値チェック】 (1D100<=52) ＞ 10 ＞ 成功",
+            ),
+            log("ソウタ", "CCB<=85 【目星】 (1D100<=85) ＞ 10 ＞ 成功"),
+        ];
+        let rolls = parse_rolls(&logs);
+        let mut rolls_by_name: HashMap<String, Vec<&Roll>> = HashMap::new();
+        for roll in &rolls {
+            rolls_by_name
+                .entry(roll.name().to_string())
+                .or_default()
+                .push(roll);
+        }
+
+        let mismatches = find_sheet_mismatches(&sheets, &rolls_by_name);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "イオリ【目星】：シート 75 / ロール 85（2件目）"
+        );
+    }
+}
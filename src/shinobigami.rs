@@ -0,0 +1,234 @@
+use crate::log::Log;
+use crate::roll::parse_skill;
+use std::fmt::{Display, Formatter};
+
+// シノビガミ・インセインの判定結果
+// どちらも2D6で、12がスペシャル、2がファンブルになる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneResult {
+    Special,
+    Success,
+    Failure,
+    Fumble,
+}
+
+impl SceneResult {
+    fn from_text(text: &str) -> Option<SceneResult> {
+        if text.starts_with("スペシャル") {
+            Some(SceneResult::Special)
+        } else if text.starts_with("ファンブル") {
+            Some(SceneResult::Fumble)
+        } else if text.contains("成功") {
+            Some(SceneResult::Success)
+        } else if text.contains("失敗") {
+            Some(SceneResult::Failure)
+        } else {
+            None
+        }
+    }
+}
+
+// 例：2D6>=5 【潜伏術】 (2D6>=5) ＞ 12[6,6] ＞ 12 ＞ スペシャル(【生命力】1点か変調一つを回復)
+#[derive(Debug, Clone)]
+pub struct SceneCheck<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    pub skill: Option<String>,
+    pub result: SceneResult,
+}
+
+impl<'a> SceneCheck<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<SceneCheck<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        let command = segments.first()?.to_uppercase();
+        if segments.len() < 3 || !command.contains("2D6") || !command.contains(">=") {
+            return None;
+        }
+        Some(SceneCheck {
+            log,
+            index,
+            skill: parse_skill(segments[0]),
+            result: SceneResult::from_text(segments[segments.len() - 1])?,
+        })
+    }
+}
+
+// シーン表・ランダム特技決定表などの表を振った結果
+// 例：ST (ST) ＞ シーン表(2) ＞ 血の臭いがあたりに充満している。
+#[derive(Debug, Clone)]
+pub struct TableRoll<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    // 「シーン表」
+    pub table: String,
+    // 「2」「1,6」
+    pub dice: String,
+    pub result: String,
+}
+
+impl<'a> TableRoll<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<TableRoll<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        let position = segments.iter().position(|s| is_table_name(s))?;
+        let result = segments[position + 1..].join(" ＞ ");
+        if result.is_empty() {
+            return None;
+        }
+        let (table, dice) = segments[position].trim_end_matches(')').split_once('(')?;
+        Some(TableRoll {
+            log,
+            index,
+            table: table.to_string(),
+            dice: dice.to_string(),
+            result,
+        })
+    }
+}
+
+// 「シーン表(2)」「ランダム特技決定表(1,6)」
+fn is_table_name(text: &str) -> bool {
+    let Some((table, dice)) = text.split_once('(') else {
+        return false;
+    };
+    table.ends_with('表')
+        && dice.strip_suffix(')').is_some_and(|dice| {
+            !dice.is_empty() && dice.chars().all(|c| c.is_ascii_digit() || c == ',')
+        })
+}
+
+pub fn parse_scene_rolls(logs: &[Log]) -> (Vec<SceneCheck<'_>>, Vec<TableRoll<'_>>) {
+    let mut checks = Vec::new();
+    let mut tables = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(check) = SceneCheck::parse(log, index, text) {
+                checks.push(check);
+            } else if let Some(table) = TableRoll::parse(log, index, text) {
+                tables.push(table);
+            }
+        }
+    }
+    (checks, tables)
+}
+
+// 一人分の集計
+#[derive(Debug, Default)]
+pub struct SceneSummary {
+    pub specials: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub fumbles: usize,
+    // 振った表と結果（振った順）
+    pub tables: Vec<(String, String)>,
+}
+
+impl SceneSummary {
+    pub fn new(checks: &[&SceneCheck], tables: &[&TableRoll]) -> SceneSummary {
+        let mut summary = SceneSummary::default();
+        for check in checks {
+            match check.result {
+                SceneResult::Special => summary.specials += 1,
+                SceneResult::Success => summary.successes += 1,
+                SceneResult::Failure => summary.failures += 1,
+                SceneResult::Fumble => summary.fumbles += 1,
+            }
+        }
+        summary.tables = tables
+            .iter()
+            .map(|table| {
+                (
+                    format!("{}({})", table.table, table.dice),
+                    table.result.clone(),
+                )
+            })
+            .collect();
+        summary
+    }
+
+    pub fn checks(&self) -> usize {
+        self.specials + self.successes + self.failures + self.fumbles
+    }
+}
+
+impl Display for SceneSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "判定：{}回（スペシャル{} / 成功{} / 失敗{} / ファンブル{}）",
+            self.checks(),
+            self.specials,
+            self.successes,
+            self.failures,
+            self.fumbles
+        )?;
+        for (table, result) in &self.tables {
+            writeln!(f, "  {}：{}", table, result)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "カゲマル".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_check() {
+        let log = log(&[
+            "2D6>=5 【潜伏術】 (2D6>=5) ＞ 12[6,6] ＞ 12 ＞ スペシャル(【生命力】1点か変調一つを回復)",
+            "2D6>=5 (2D6>=5) ＞ 2[1,1] ＞ 2 ＞ ファンブル",
+            "2D6>=5 (2D6>=5) ＞ 8[3,5] ＞ 8 ＞ 成功",
+        ]);
+        let (checks, _) = parse_scene_rolls(std::slice::from_ref(&log));
+        let results: Vec<SceneResult> = checks.iter().map(|check| check.result).collect();
+        assert_eq!(
+            results,
+            vec![
+                SceneResult::Special,
+                SceneResult::Fumble,
+                SceneResult::Success
+            ]
+        );
+        assert_eq!(checks[0].skill, Some("潜伏術".to_string()));
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let log = log(&[
+            "ST (ST) ＞ シーン表(2) ＞ 血の臭いがあたりに充満している。",
+            "RTT ＞ ランダム特技決定表(1,6) ＞ 『器術』屋外",
+            "1d6 (1D6) ＞ 3",
+        ]);
+        let (_, tables) = parse_scene_rolls(std::slice::from_ref(&log));
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].table, "シーン表");
+        assert_eq!(tables[0].dice, "2");
+        assert_eq!(tables[0].result, "血の臭いがあたりに充満している。");
+        assert_eq!(tables[1].dice, "1,6");
+    }
+
+    #[test]
+    fn test_scene_summary() {
+        let log = log(&[
+            "2D6>=5 (2D6>=5) ＞ 12[6,6] ＞ 12 ＞ スペシャル",
+            "2D6>=5 (2D6>=5) ＞ 4[1,3] ＞ 4 ＞ 失敗",
+            "RTT ＞ ランダム特技決定表(1,6) ＞ 『器術』屋外",
+        ]);
+        let (checks, tables) = parse_scene_rolls(std::slice::from_ref(&log));
+        let checks: Vec<&SceneCheck> = checks.iter().collect();
+        let tables: Vec<&TableRoll> = tables.iter().collect();
+        assert_eq!(
+            SceneSummary::new(&checks, &tables).to_string(),
+            "判定：2回（スペシャル1 / 成功0 / 失敗1 / ファンブル0）\n  ランダム特技決定表(1,6)：『器術』屋外\n"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+// 統計用の小さな関数群
+
+// カイ二乗統計量
+pub fn chi_square(observed: &[usize], expected: &[f64]) -> f64 {
+    observed
+        .iter()
+        .zip(expected)
+        .filter(|(_, e)| **e > 0.0)
+        .map(|(o, e)| (*o as f64 - e).powi(2) / e)
+        .sum()
+}
+
+// 自由度dfのカイ二乗分布で統計量がx以上になる確率（p値）
+pub fn chi_square_p_value(x: f64, df: usize) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    upper_incomplete_gamma(df as f64 / 2.0, x / 2.0)
+}
+
+// 正規化された上側不完全ガンマ関数 Q(a, x)
+// 参考：Numerical Recipes 6.2
+fn upper_incomplete_gamma(a: f64, x: f64) -> f64 {
+    if x < a + 1.0 {
+        1.0 - gamma_series(a, x)
+    } else {
+        gamma_continued_fraction(a, x)
+    }
+}
+
+fn gamma_series(a: f64, x: f64) -> f64 {
+    let mut sum = 1.0 / a;
+    let mut term = sum;
+    let mut n = a;
+    for _ in 0..1000 {
+        n += 1.0;
+        term *= x / n;
+        sum += term;
+        if term.abs() < sum.abs() * 1e-15 {
+            break;
+        }
+    }
+    sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+fn gamma_continued_fraction(a: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..1000 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+// Lanczos近似によるlnΓ(x)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    let mut y = x;
+    for coefficient in COEFFICIENTS {
+        y += 1.0;
+        series += coefficient / y;
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_chi_square() {
+        assert_close(chi_square(&[10, 10], &[10.0, 10.0]), 0.0);
+        assert_close(chi_square(&[15, 5], &[10.0, 10.0]), 5.0);
+    }
+
+    #[test]
+    fn test_chi_square_p_value() {
+        // 自由度9の5%点は16.919
+        assert_close(chi_square_p_value(16.919, 9), 0.05);
+        // 自由度1の5%点は3.841
+        assert_close(chi_square_p_value(3.841, 1), 0.05);
+        assert_close(chi_square_p_value(0.0, 9), 1.0);
+        assert_close(chi_square_p_value(2.0, 2), (-1.0f64).exp());
+    }
+}
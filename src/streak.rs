@@ -0,0 +1,145 @@
+use crate::roll::Roll;
+use std::fmt::{Display, Formatter};
+
+// 連続した成功または失敗
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Streak {
+    pub length: usize,
+    // 始まりと終わりのロールのログ内の位置
+    pub start: usize,
+    pub end: usize,
+}
+
+// 一人分の最長連続成功・失敗
+pub struct Streaks {
+    pub success: Option<Streak>,
+    pub failure: Option<Streak>,
+}
+
+impl Streaks {
+    // rollsは一人分を時系列順に渡す
+    pub fn new(rolls: &[&Roll]) -> Streaks {
+        let mut streaks = Streaks {
+            success: None,
+            failure: None,
+        };
+        let mut current: Option<(bool, Streak)> = None;
+
+        for roll in rolls {
+            let success = roll.result.is_success();
+            let streak = match current {
+                Some((s, streak)) if s == success => Streak {
+                    length: streak.length + 1,
+                    end: roll.index,
+                    ..streak
+                },
+                _ => Streak {
+                    length: 1,
+                    start: roll.index,
+                    end: roll.index,
+                },
+            };
+            current = Some((success, streak));
+
+            let longest = if success {
+                &mut streaks.success
+            } else {
+                &mut streaks.failure
+            };
+            // 同じ長さなら先に起きた方を残す
+            if longest.is_none_or(|longest| streak.length > longest.length) {
+                *longest = Some(streak);
+            }
+        }
+        streaks
+    }
+}
+
+impl Display for Streaks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::new();
+        for (label, streak) in [("成功", &self.success), ("失敗", &self.failure)] {
+            match streak {
+                // 位置は1から数える
+                Some(streak) if streak.start == streak.end => s.push_str(&format!(
+                    "最長連続{}：{}回（{}件目のチャット）\n",
+                    label,
+                    streak.length,
+                    streak.start + 1
+                )),
+                Some(streak) => s.push_str(&format!(
+                    "最長連続{}：{}回（{}～{}件目のチャット）\n",
+                    label,
+                    streak.length,
+                    streak.start + 1,
+                    streak.end + 1
+                )),
+                None => s.push_str(&format!("最長連続{}：0回\n", label)),
+            }
+        }
+        write!(f, "{s}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(text: &str) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "PC1".to_string(),
+            texts: vec![text.to_string()],
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_streaks() {
+        let logs = vec![
+            log("CCB<=50 (1D100<=50) ＞ 10 ＞ 成功"),
+            log("CCB<=50 (1D100<=50) ＞ 60 ＞ 失敗"),
+            log("雑談"),
+            log("CCB<=50 (1D100<=50) ＞ 99 ＞ 致命的失敗"),
+            log("CCB<=50 (1D100<=50) ＞ 70 ＞ 失敗"),
+            log("CCB<=50 (1D100<=50) ＞ 3 ＞ 決定的成功/スペシャル"),
+            log("CCB<=50 (1D100<=50) ＞ 20 ＞ 成功"),
+        ];
+        let rolls = parse_rolls(&logs);
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let streaks = Streaks::new(&rolls);
+
+        assert_eq!(
+            streaks.failure,
+            Some(Streak {
+                length: 3,
+                start: 1,
+                end: 4
+            })
+        );
+        assert_eq!(
+            streaks.success,
+            Some(Streak {
+                length: 2,
+                start: 5,
+                end: 6
+            })
+        );
+        assert_eq!(
+            streaks.to_string(),
+            "最長連続成功：2回（6～7件目のチャット）\n最長連続失敗：3回（2～5件目のチャット）\n"
+        );
+    }
+
+    #[test]
+    fn test_no_rolls() {
+        let streaks = Streaks::new(&[]);
+        assert_eq!(streaks.success, None);
+        assert_eq!(
+            streaks.to_string(),
+            "最長連続成功：0回\n最長連続失敗：0回\n"
+        );
+    }
+}
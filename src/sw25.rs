@@ -0,0 +1,245 @@
+use crate::log::Log;
+use crate::roll::parse_skill;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sw25Result {
+    // 6ゾロ
+    AutoSuccess,
+    Success,
+    Failure,
+    // 1ゾロ
+    AutoFailure,
+}
+
+impl Sw25Result {
+    fn from_text(text: &str) -> Option<Sw25Result> {
+        if text.contains("自動的成功") || text.contains("自動成功") {
+            Some(Sw25Result::AutoSuccess)
+        } else if text.contains("自動的失敗") || text.contains("自動失敗") {
+            Some(Sw25Result::AutoFailure)
+        } else if text.contains("成功") {
+            Some(Sw25Result::Success)
+        } else if text.contains("失敗") {
+            Some(Sw25Result::Failure)
+        } else {
+            None
+        }
+    }
+}
+
+// 行為判定一回分
+// 例：2d6+5>=12 【回避】 (2D6+5>=12) ＞ 7[3,4]+5 ＞ 12 ＞ 成功
+#[derive(Debug, Clone)]
+pub struct Sw25Check<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    pub skill: Option<String>,
+    pub total: Option<i32>,
+    pub result: Sw25Result,
+}
+
+impl<'a> Sw25Check<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<Sw25Check<'a>> {
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        let command = segments.first()?.to_uppercase();
+        if segments.len() < 3 || !command.contains("2D6") || !command.contains(">=") {
+            return None;
+        }
+        let result = Sw25Result::from_text(segments[segments.len() - 1])?;
+        Some(Sw25Check {
+            log,
+            index,
+            skill: parse_skill(segments[0]),
+            total: segments[segments.len() - 2].parse().ok(),
+            result,
+        })
+    }
+}
+
+// 威力表を使ったダメージロール一回分
+// 例：K20+10 (KeyNo.20c[10]+10) ＞ 2D:[5,5 3,4]=10,7 ＞ 6,5+10 ＞ 1回転 ＞ 21
+#[derive(Debug, Clone)]
+pub struct Sw25Damage<'a> {
+    pub log: &'a Log,
+    pub index: usize,
+    // 1ゾロのときはNone
+    pub total: Option<u32>,
+    // クリティカルで威力表を振り足した回数
+    pub rotations: u32,
+    pub fumble: bool,
+}
+
+impl<'a> Sw25Damage<'a> {
+    pub fn parse(log: &'a Log, index: usize, text: &str) -> Option<Sw25Damage<'a>> {
+        if !text.contains("KeyNo.") {
+            return None;
+        }
+        let segments: Vec<&str> = text.split('＞').map(|s| s.trim()).collect();
+        if segments.len() < 3 {
+            return None;
+        }
+        let last = segments[segments.len() - 1];
+        let rotations = segments
+            .iter()
+            .find_map(|s| s.strip_suffix("回転")?.parse().ok())
+            .unwrap_or(0);
+        Some(Sw25Damage {
+            log,
+            index,
+            total: last.parse().ok(),
+            rotations,
+            fumble: last.contains("自動的失敗") || last.contains("自動失敗"),
+        })
+    }
+}
+
+// 一人分の集計
+#[derive(Debug, Default)]
+pub struct Sw25Summary {
+    pub auto_successes: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub auto_failures: usize,
+    pub damage_rolls: usize,
+    pub damage_total: u32,
+    pub max_damage: u32,
+    pub rotations: u32,
+    // ダメージロールでの1ゾロ
+    pub damage_fumbles: usize,
+}
+
+impl Sw25Summary {
+    pub fn new(checks: &[&Sw25Check], damages: &[&Sw25Damage]) -> Sw25Summary {
+        let mut summary = Sw25Summary::default();
+        for check in checks {
+            match check.result {
+                Sw25Result::AutoSuccess => summary.auto_successes += 1,
+                Sw25Result::Success => summary.successes += 1,
+                Sw25Result::Failure => summary.failures += 1,
+                Sw25Result::AutoFailure => summary.auto_failures += 1,
+            }
+        }
+        for damage in damages {
+            summary.damage_rolls += 1;
+            summary.rotations += damage.rotations;
+            if damage.fumble {
+                summary.damage_fumbles += 1;
+            }
+            if let Some(total) = damage.total {
+                summary.damage_total += total;
+                summary.max_damage = summary.max_damage.max(total);
+            }
+        }
+        summary
+    }
+
+    pub fn checks(&self) -> usize {
+        self.auto_successes + self.successes + self.failures + self.auto_failures
+    }
+}
+
+impl Display for Sw25Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "判定：{}回（自動成功{} / 成功{} / 失敗{} / 自動失敗{}）",
+            self.checks(),
+            self.auto_successes,
+            self.successes,
+            self.failures,
+            self.auto_failures
+        )?;
+        if self.damage_rolls > 0 {
+            writeln!(
+                f,
+                "ダメージ：{}回（合計{}、最大{}、回転{}回、1ゾロ{}回）",
+                self.damage_rolls,
+                self.damage_total,
+                self.max_damage,
+                self.rotations,
+                self.damage_fumbles
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_sw25_rolls(logs: &[Log]) -> (Vec<Sw25Check<'_>>, Vec<Sw25Damage<'_>>) {
+    let mut checks = Vec::new();
+    let mut damages = Vec::new();
+    for (index, log) in logs.iter().enumerate() {
+        for text in &log.texts {
+            if let Some(check) = Sw25Check::parse(log, index, text) {
+                checks.push(check);
+            } else if let Some(damage) = Sw25Damage::parse(log, index, text) {
+                damages.push(damage);
+            }
+        }
+    }
+    (checks, damages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "アルト".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_check() {
+        let log = log(&[
+            "2d6+5>=12 【回避】 (2D6+5>=12) ＞ 7[3,4]+5 ＞ 12 ＞ 成功",
+            "2d6+5>=12 (2D6+5>=12) ＞ 12[6,6]+5 ＞ 17 ＞ 自動的成功",
+            "2d6+5 (2D6+5) ＞ 7[3,4]+5 ＞ 12",
+        ]);
+        let check = Sw25Check::parse(&log, 0, &log.texts[0]).unwrap();
+        assert_eq!(check.skill, Some("回避".to_string()));
+        assert_eq!(check.total, Some(12));
+        assert_eq!(check.result, Sw25Result::Success);
+        let check = Sw25Check::parse(&log, 0, &log.texts[1]).unwrap();
+        assert_eq!(check.result, Sw25Result::AutoSuccess);
+        assert!(Sw25Check::parse(&log, 0, &log.texts[2]).is_none());
+    }
+
+    #[test]
+    fn test_parse_damage() {
+        let log = log(&[
+            "K20+10 (KeyNo.20c[10]+10) ＞ 2D:[5,5 3,4]=10,7 ＞ 6,5+10 ＞ 1回転 ＞ 21",
+            "K20+10 (KeyNo.20+10) ＞ 2D:[1,1]=2 ＞ ** ＞ 自動的失敗",
+        ]);
+        let damage = Sw25Damage::parse(&log, 0, &log.texts[0]).unwrap();
+        assert_eq!(damage.total, Some(21));
+        assert_eq!(damage.rotations, 1);
+        assert!(!damage.fumble);
+        let damage = Sw25Damage::parse(&log, 0, &log.texts[1]).unwrap();
+        assert_eq!(damage.total, None);
+        assert!(damage.fumble);
+    }
+
+    #[test]
+    fn test_sw25_summary() {
+        let log = log(&[
+            "2d6+5>=12 (2D6+5>=12) ＞ 7[3,4]+5 ＞ 12 ＞ 成功",
+            "2d6+5>=12 (2D6+5>=12) ＞ 2[1,1]+5 ＞ 7 ＞ 自動的失敗",
+            "2d6+5>=12 (2D6+5>=12) ＞ 5[1,4]+5 ＞ 10 ＞ 失敗",
+            "K20+10 (KeyNo.20c[10]+10) ＞ 2D:[5,5 3,4]=10,7 ＞ 6,5+10 ＞ 1回転 ＞ 21",
+            "K20+10 (KeyNo.20+10) ＞ 2D:[3,4]=7 ＞ 5+10 ＞ 15",
+        ]);
+        let (checks, damages) = parse_sw25_rolls(std::slice::from_ref(&log));
+        let checks: Vec<&Sw25Check> = checks.iter().collect();
+        let damages: Vec<&Sw25Damage> = damages.iter().collect();
+        let summary = Sw25Summary::new(&checks, &damages);
+        assert_eq!(
+            summary.to_string(),
+            "判定：3回（自動成功0 / 成功1 / 失敗1 / 自動失敗1）\nダメージ：2回（合計36、最大21、回転1回、1ゾロ0回）\n"
+        );
+    }
+}
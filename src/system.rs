@@ -0,0 +1,98 @@
+use crate::error::ParseError;
+
+// 集計に使うゲームシステム
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameSystem {
+    // クトゥルフ神話TRPG（6版）
+    Coc6,
+    // エモクロアTRPG（成功数で判定する）
+    Emoklore,
+    // ソード・ワールド2.5（2D6判定と威力表）
+    Sw25,
+    // ダブルクロス3rd（達成値とクリティカル値）
+    Dx3,
+    // シノビガミ・インセイン（2D6判定とシーン表）
+    Shinobigami,
+    Insane,
+}
+
+impl GameSystem {
+    pub fn from_name(name: &str) -> Result<GameSystem, ParseError> {
+        match name.trim().to_lowercase().as_str() {
+            "coc6" | "cthulhu" => Ok(GameSystem::Coc6),
+            "emoklore" | "エモクロア" => Ok(GameSystem::Emoklore),
+            "sw25" | "sw2.5" | "swordworld2.5" => Ok(GameSystem::Sw25),
+            "dx3" | "doublecross" => Ok(GameSystem::Dx3),
+            "shinobigami" | "シノビガミ" => Ok(GameSystem::Shinobigami),
+            "insane" | "インセイン" => Ok(GameSystem::Insane),
+            _ => Err(ParseError {
+                string: format!("不明なゲームシステムです：{}", name),
+            }),
+        }
+    }
+
+    pub fn to_display_string(&self) -> &str {
+        match self {
+            GameSystem::Coc6 => "クトゥルフ神話TRPG（6版）",
+            GameSystem::Emoklore => "エモクロアTRPG",
+            GameSystem::Sw25 => "ソード・ワールド2.5",
+            GameSystem::Dx3 => "ダブルクロス3rd",
+            GameSystem::Shinobigami => "シノビガミ",
+            GameSystem::Insane => "インセイン",
+        }
+    }
+
+    // 目標値targetの1D100判定が成功（クリティカルを含む）する確率
+    // 1D100判定のないシステムでも素の1D100として扱う
+    pub fn success_probability(&self, target: u32) -> f64 {
+        target.min(100) as f64 / 100.0
+    }
+
+    // 目標値targetの1D100判定がクリティカルになる確率
+    // CoC6：出目1～5かつ成功
+    pub fn critical_probability(&self, target: u32) -> f64 {
+        match self {
+            GameSystem::Coc6 => target.min(5) as f64 / 100.0,
+            _ => 0.0,
+        }
+    }
+
+    // 目標値targetの1D100判定がファンブルになる確率
+    // CoC6：出目96～100かつ失敗
+    pub fn fumble_probability(&self, target: u32) -> f64 {
+        match self {
+            GameSystem::Coc6 => (100 - target.clamp(95, 100)) as f64 / 100.0,
+            _ => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(GameSystem::from_name("CoC6").unwrap(), GameSystem::Coc6);
+        assert_eq!(
+            GameSystem::from_name("Emoklore").unwrap(),
+            GameSystem::Emoklore
+        );
+        assert_eq!(GameSystem::from_name("SW2.5").unwrap(), GameSystem::Sw25);
+        assert_eq!(GameSystem::from_name("dx3").unwrap(), GameSystem::Dx3);
+        assert_eq!(GameSystem::from_name("insane").unwrap(), GameSystem::Insane);
+        assert!(GameSystem::from_name("unknown").is_err());
+    }
+
+    #[test]
+    fn test_coc6_probabilities() {
+        let system = GameSystem::Coc6;
+        assert_eq!(system.success_probability(25), 0.25);
+        assert_eq!(system.success_probability(150), 1.0);
+        assert_eq!(system.critical_probability(50), 0.05);
+        assert_eq!(system.critical_probability(3), 0.03);
+        assert_eq!(system.fumble_probability(50), 0.05);
+        assert_eq!(system.fumble_probability(97), 0.03);
+        assert_eq!(system.fumble_probability(120), 0.0);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::error::ParseError;
+use crate::roll::{strip_secret_prefix, Roll, RollResult};
+use crate::system::GameSystem;
+use std::fmt::{Display, Formatter};
+
+// 1D100判定のクリティカル・ファンブルの範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CriticalRule {
+    // CCB：出目1～5かつ成功でクリティカル、出目96～100かつ失敗でファンブル
+    Coc6,
+    // CC：出目1でクリティカル、出目100でファンブル
+    Strict,
+    // 1D100<=50：クリティカル・ファンブルなし
+    None,
+    // ハウスルール：目標値の1/10以下でクリティカル、失敗する範囲の1/10でファンブル（どちらも最低1）
+    // 目標値50なら1～5/96～100、目標値80なら1～8/99～100
+    Scaled,
+}
+
+impl CriticalRule {
+    // BCDiceはコマンドでクリティカル・ファンブルの範囲を変える
+    pub fn from_command(command: &str) -> CriticalRule {
+        let command = command.trim().to_uppercase();
+        let command = strip_secret_prefix(&command).unwrap_or(&command);
+        if ["CCB", "CBRB", "RESB"]
+            .iter()
+            .any(|prefix| command.starts_with(prefix))
+        {
+            CriticalRule::Coc6
+        } else if ["CC", "CBR", "RES"]
+            .iter()
+            .any(|prefix| command.starts_with(prefix))
+        {
+            CriticalRule::Strict
+        } else {
+            CriticalRule::None
+        }
+    }
+
+    // BCDiceのクトゥルフ神話TRPGでは「1D100<=50」もCCBと同じ範囲で判定する
+    pub fn for_roll(command: &str, system: GameSystem) -> CriticalRule {
+        match CriticalRule::from_command(command) {
+            CriticalRule::None if system == GameSystem::Coc6 => CriticalRule::Coc6,
+            rule => rule,
+        }
+    }
+
+    // 設定ファイルの「critical_rule」の値
+    pub fn from_name(name: &str) -> Result<CriticalRule, ParseError> {
+        match name.trim().to_lowercase().as_str() {
+            "coc6" | "1-5/96-100" => Ok(CriticalRule::Coc6),
+            "strict" | "01/100" | "1/100" => Ok(CriticalRule::Strict),
+            "none" => Ok(CriticalRule::None),
+            "scaled" => Ok(CriticalRule::Scaled),
+            _ => Err(ParseError {
+                string: format!("不明なクリティカル・ファンブルのルールです：{}", name),
+            }),
+        }
+    }
+
+    pub fn to_display_string(&self) -> &str {
+        match self {
+            CriticalRule::Coc6 => "1～5/96～100",
+            CriticalRule::Strict => "01/100",
+            CriticalRule::None => "クリティカル・ファンブルなし",
+            CriticalRule::Scaled => "目標値の1/10",
+        }
+    }
+
+    // 出目と目標値から本来の結果を求める
+    // 目標値に関係なく100は失敗になる
+    pub fn result(&self, value: u32, target: u32) -> RollResult {
+        let (critical, fumble) = match self {
+            CriticalRule::Coc6 => (5, 96),
+            CriticalRule::Strict => (1, 100),
+            CriticalRule::None => (0, 101),
+            CriticalRule::Scaled => {
+                let target = target.min(100);
+                ((target / 10).max(1), 101 - ((100 - target) / 10).max(1))
+            }
+        };
+        if value <= target && value < 100 {
+            if value <= critical {
+                RollResult::Critical
+            } else {
+                RollResult::Success
+            }
+        } else if value >= fumble {
+            RollResult::Fumble
+        } else {
+            RollResult::Failure
+        }
+    }
+
+    // 目標値targetで振ったときにクリティカル・ファンブルになる確率
+    pub fn probabilities(&self, target: u32) -> (f64, f64) {
+        let (mut criticals, mut fumbles) = (0, 0);
+        for value in 1..=100 {
+            match self.result(value, target) {
+                RollResult::Critical => criticals += 1,
+                RollResult::Fumble => fumbles += 1,
+                _ => {}
+            }
+        }
+        (criticals as f64 / 100.0, fumbles as f64 / 100.0)
+    }
+}
+
+// 出目と目標値から求めた結果と、ログに書かれた結果が食い違っているロール
+pub struct ResultMismatch<'a> {
+    pub roll: Roll<'a>,
+    pub expected: RollResult,
+}
+
+impl Display for ResultMismatch<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}（{}件目）：{}\n  ログでは{}、出目{}・目標値{}なら{}",
+            self.roll.name(),
+            self.roll.index + 1,
+            self.roll.text,
+            self.roll.result.to_display_string(),
+            self.roll.value.unwrap_or(0),
+            self.roll.target.unwrap_or(0),
+            self.expected.to_display_string()
+        )
+    }
+}
+
+// 出目と目標値が読み取れる1D100のロールだけを確かめる
+// ハウスルールが指定されていればそれで、なければシステムとコマンドから決まる範囲で判定する
+pub fn find_result_mismatches<'a>(
+    rolls: &[Roll<'a>],
+    system: GameSystem,
+    house_rule: Option<CriticalRule>,
+) -> Vec<ResultMismatch<'a>> {
+    rolls
+        .iter()
+        .filter(|roll| roll.is_d100())
+        .filter_map(|roll| {
+            let (Some(value), Some(target)) = (roll.value, roll.target) else {
+                return None;
+            };
+            let rule = house_rule.unwrap_or(CriticalRule::for_roll(&roll.command, system));
+            let expected = rule.result(value, target);
+            (expected != roll.result).then(|| ResultMismatch {
+                roll: roll.clone(),
+                expected,
+            })
+        })
+        .collect()
+}
+
+// ログに書かれた結果と、ハウスルールで判定し直した結果の集計
+#[derive(Debug)]
+pub struct HouseRuleSummary {
+    pub rule: CriticalRule,
+    pub printed_criticals: usize,
+    pub printed_fumbles: usize,
+    pub criticals: usize,
+    pub fumbles: usize,
+    // 判定し直して結果が変わったロールの数
+    pub changed: usize,
+}
+
+impl HouseRuleSummary {
+    // 出目と目標値が読み取れる1D100のロールだけを数える
+    pub fn new(rolls: &[&Roll], rule: CriticalRule) -> HouseRuleSummary {
+        let mut summary = HouseRuleSummary {
+            rule,
+            printed_criticals: 0,
+            printed_fumbles: 0,
+            criticals: 0,
+            fumbles: 0,
+            changed: 0,
+        };
+        // 組み合わせロールは一つの出目なので一度だけ数える
+        for roll in rolls
+            .iter()
+            .filter(|roll| roll.is_d100() && roll.is_first_part())
+        {
+            let (Some(value), Some(target)) = (roll.value, roll.target) else {
+                continue;
+            };
+            let result = rule.result(value, target);
+            match roll.result {
+                RollResult::Critical => summary.printed_criticals += 1,
+                RollResult::Fumble => summary.printed_fumbles += 1,
+                _ => {}
+            }
+            match result {
+                RollResult::Critical => summary.criticals += 1,
+                RollResult::Fumble => summary.fumbles += 1,
+                _ => {}
+            }
+            if result != roll.result {
+                summary.changed += 1;
+            }
+        }
+        summary
+    }
+}
+
+impl Display for HouseRuleSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "ハウスルール（{}）：クリティカル{} → {} / ファンブル{} → {}（結果が変わったロール{}件）",
+            self.rule.to_display_string(),
+            self.printed_criticals,
+            self.criticals,
+            self.printed_fumbles,
+            self.fumbles,
+            self.changed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::Log;
+    use crate::roll::parse_rolls;
+
+    fn log(texts: &[&str]) -> Log {
+        Log {
+            tab: "メイン".to_string(),
+            name: "PC1".to_string(),
+            texts: texts.iter().map(|text| text.to_string()).collect(),
+            color: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_from_command() {
+        assert_eq!(
+            CriticalRule::from_command("CCB<=50 (1D100<=50)"),
+            CriticalRule::Coc6
+        );
+        assert_eq!(
+            CriticalRule::from_command("S CCB<=50 (1D100<=50)"),
+            CriticalRule::Coc6
+        );
+        assert_eq!(
+            CriticalRule::from_command("CC<=50 (1D100<=50)"),
+            CriticalRule::Strict
+        );
+        assert_eq!(
+            CriticalRule::from_command("1d100<=50 (1D100<=50)"),
+            CriticalRule::None
+        );
+    }
+
+    #[test]
+    fn test_result() {
+        let rule = CriticalRule::Coc6;
+        assert_eq!(rule.result(5, 50), RollResult::Critical);
+        assert_eq!(rule.result(6, 50), RollResult::Success);
+        assert_eq!(rule.result(4, 3), RollResult::Failure);
+        assert_eq!(rule.result(96, 50), RollResult::Fumble);
+        assert_eq!(rule.result(97, 99), RollResult::Success);
+        assert_eq!(rule.result(100, 120), RollResult::Fumble);
+
+        let rule = CriticalRule::Strict;
+        assert_eq!(rule.result(2, 50), RollResult::Success);
+        assert_eq!(rule.result(1, 50), RollResult::Critical);
+        assert_eq!(rule.result(99, 50), RollResult::Failure);
+        assert_eq!(rule.result(100, 50), RollResult::Fumble);
+
+        let rule = CriticalRule::Scaled;
+        assert_eq!(rule.result(5, 50), RollResult::Critical);
+        assert_eq!(rule.result(96, 50), RollResult::Fumble);
+        assert_eq!(rule.result(8, 80), RollResult::Critical);
+        assert_eq!(rule.result(98, 80), RollResult::Failure);
+        assert_eq!(rule.result(1, 5), RollResult::Critical);
+        assert_eq!(rule.result(2, 5), RollResult::Success);
+        assert_eq!(rule.result(91, 5), RollResult::Failure);
+        assert_eq!(rule.result(92, 5), RollResult::Fumble);
+
+        assert_eq!(CriticalRule::None.result(1, 50), RollResult::Success);
+        assert_eq!(CriticalRule::None.result(100, 50), RollResult::Failure);
+    }
+
+    #[test]
+    fn test_find_result_mismatches() {
+        let log = log(&[
+            "CCB<=50 (1D100<=50) ＞ 3 ＞ 決定的成功/スペシャル",
+            "CCB<=50 (1D100<=50) ＞ 60 ＞ 成功",
+            "CC<=50 (1D100<=50) ＞ 3 ＞ 決定的成功",
+            "CCB<=50 (1D100<=50) ＞ 97 ＞ 失敗",
+            "2d6+5>=12 (2D6+5>=12) ＞ 7[3,4]+5 ＞ 12 ＞ 失敗",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        let mismatches = find_result_mismatches(&rolls, GameSystem::Coc6, None);
+        let expected: Vec<(&str, RollResult)> = mismatches
+            .iter()
+            .map(|mismatch| (mismatch.roll.text, mismatch.expected))
+            .collect();
+        assert_eq!(
+            expected,
+            vec![
+                ("CCB<=50 (1D100<=50) ＞ 60 ＞ 成功", RollResult::Failure),
+                ("CC<=50 (1D100<=50) ＞ 3 ＞ 決定的成功", RollResult::Success),
+                ("CCB<=50 (1D100<=50) ＞ 97 ＞ 失敗", RollResult::Fumble),
+            ]
+        );
+        assert_eq!(
+            mismatches[0].to_string(),
+            "PC1（1件目）：CCB<=50 (1D100<=50) ＞ 60 ＞ 成功\n  ログでは成功、出目60・目標値50なら失敗"
+        );
+    }
+
+    #[test]
+    fn test_plain_d100_in_coc6() {
+        let log = log(&[
+            "1d100<=50 【SANチェック】 (1D100<=50) ＞ 98 ＞ 致命的失敗",
+            "1d100<=50 (1D100<=50) ＞ 3 ＞ 決定的成功/スペシャル",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        assert!(find_result_mismatches(&rolls, GameSystem::Coc6, None).is_empty());
+        assert_eq!(
+            find_result_mismatches(&rolls, GameSystem::Emoklore, None).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_house_rule_mismatches() {
+        let log = log(&["CCB<=50 (1D100<=50) ＞ 3 ＞ 決定的成功/スペシャル"]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        let mismatches =
+            find_result_mismatches(&rolls, GameSystem::Coc6, Some(CriticalRule::Strict));
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].expected, RollResult::Success);
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            CriticalRule::from_name("01/100").unwrap(),
+            CriticalRule::Strict
+        );
+        assert_eq!(
+            CriticalRule::from_name("Scaled").unwrap(),
+            CriticalRule::Scaled
+        );
+        assert!(CriticalRule::from_name("1-10").is_err());
+    }
+
+    #[test]
+    fn test_house_rule_summary() {
+        let log = log(&[
+            "CCB<=50 (1D100<=50) ＞ 3 ＞ 決定的成功/スペシャル",
+            "CCB<=50 (1D100<=50) ＞ 97 ＞ 致命的失敗",
+            "CCB<=50 (1D100<=50) ＞ 100 ＞ 致命的失敗",
+            "CCB<=50 (1D100<=50) ＞ 1 ＞ 決定的成功/スペシャル",
+        ]);
+        let rolls = parse_rolls(std::slice::from_ref(&log));
+        let rolls: Vec<&Roll> = rolls.iter().collect();
+        let summary = HouseRuleSummary::new(&rolls, CriticalRule::Strict);
+        assert_eq!(
+            summary.to_string(),
+            "ハウスルール（01/100）：クリティカル2 → 1 / ファンブル2 → 1（結果が変わったロール2件）\n"
+        );
+    }
+}